@@ -1,31 +1,53 @@
-use std::{array::TryFromSliceError, fmt::Display};
+use core::{array::TryFromSliceError, fmt::Display};
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct TryFromPacketError {
-    pub seqnum: u8,
+    /// Sequence number of the packet to acknowledge, `None` if the frame itself is invalid
+    /// or the packet is not acknowledged.
+    pub seqnum: Option<u8>,
     pub error: Error,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Error {
     UnknownPacket(&'static str),
-    PacketPending,
+    QueueFull,
     InvalidValueForEnum { what: &'static str, value: u8 },
     UnknownPayloadType(u8),
     MissingBytes,
     NotImplemented(&'static str),
+    InvalidHeader(u8),
+    InvalidChecksum { expected: u8, actual: u8 },
+    InvalidTrailer(u8),
+    BufferTooSmall { needed: usize, available: usize },
+    InvalidString,
+    OutOfRange { what: &'static str, value: i32 },
 }
 
 impl Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::UnknownPacket(name) => write!(f, "unknown packet (type = \"{}\")", name),
-            Self::PacketPending => write!(f, "already in sending state"),
+            Self::QueueFull => write!(f, "too many packets waiting to be sent"),
             Self::InvalidValueForEnum { what, value } => {
                 write!(f, "invalid value for {} : {:02x}", what, value)
             }
             Self::UnknownPayloadType(t) => write!(f, "unknown payload type : {:02x?}", t),
             Self::MissingBytes => write!(f, "missing data to parse packet"),
             Self::NotImplemented(what) => write!(f, "{} is not implemented", what),
+            Self::InvalidHeader(h) => write!(f, "invalid packet header : {:02x}", h),
+            Self::InvalidChecksum { expected, actual } => write!(
+                f,
+                "invalid packet checksum : expected {:02x}, got {:02x}",
+                expected, actual
+            ),
+            Self::InvalidTrailer(t) => write!(f, "invalid packet trailer : {:02x}", t),
+            Self::BufferTooSmall { needed, available } => write!(
+                f,
+                "buffer too small : {} bytes needed, {} available",
+                needed, available
+            ),
+            Self::InvalidString => write!(f, "string is not valid UTF-8"),
+            Self::OutOfRange { what, value } => write!(f, "{} out of range : {}", what, value),
         }
     }
 }
@@ -36,6 +58,7 @@ impl From<TryFromSliceError> for Error {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
 impl From<&Error> for Error {
@@ -44,4 +67,4 @@ impl From<&Error> for Error {
     }
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
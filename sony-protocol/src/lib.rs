@@ -1,151 +1,511 @@
-use std::ops::{Not, Range};
+#![cfg_attr(not(feature = "std"), no_std)]
 
-// TODO: maybe find a way to not depend on time api
-#[cfg(not(target_family = "wasm"))]
-use std::time::{Duration, Instant};
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
-#[cfg(target_family = "wasm")]
-use web_time::{Duration, Instant};
+use core::{
+    ops::{Not, Range},
+    time::Duration,
+};
 
-use tracing::{trace, warn};
 use v1::{Packet, PacketContent};
 
+#[macro_use]
+mod log;
+
 mod error;
 pub mod v1;
 
 pub use error::{Error, Result, TryFromPacketError};
 
+// Received packets are stored inline so that decoding needs no allocator.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum State<'a> {
-    WaitingPacket(Option<Instant>),
+    WaitingPacket(Option<Timestamp>),
     ReceivedPacket(crate::v1::Packet),
-    SendPacket(&'a [u8]),
+    /// Bytes to write to the device, with the id of the queued packet they carry, or
+    /// `None` for an ACK.
+    SendPacket(&'a [u8], Option<PacketId>),
+    /// The packet was acknowledged by the device, or sent if it is a shot packet that
+    /// does not expect any acknowledgement.
+    PacketAcked(PacketId),
+    /// The packet could not be encoded or was not acknowledged after the last attempt
+    /// allowed by the [`RetryPolicy`].
+    PacketFailed(PacketId),
+}
+
+/// A point in time given by the caller, in milliseconds since an arbitrary origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    pub const fn from_millis(millis: u64) -> Self {
+        Self(millis)
+    }
+
+    pub const fn as_millis(&self) -> u64 {
+        self.0
+    }
+
+    fn saturating_add(self, duration: Duration) -> Self {
+        let millis = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        Self(self.0.saturating_add(millis))
+    }
+}
+
+/// Identifies a packet queued with [`Device::send_packet`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PacketId(u32);
+
+/// How a packet is retransmitted while waiting for its ACK.
+///
+/// Values that would retransmit without waiting are raised by [`Device::new`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Time to wait for the ACK after the first transmission, at least 1 ms.
+    pub initial_timeout: Duration,
+    /// Factor applied to the timeout after each retransmission, at least 1.
+    pub backoff: u32,
+    /// Number of transmissions, including the first one, before giving up, at least 1.
+    pub max_attempts: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_timeout: Duration::from_secs(1),
+            backoff: 1,
+            max_attempts: 5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn clamped(self) -> Self {
+        Self {
+            // Timestamps are in milliseconds, a shorter timeout would expire at once.
+            initial_timeout: self.initial_timeout.max(Duration::from_millis(1)),
+            backoff: self.backoff.max(1),
+            max_attempts: self.max_attempts.max(1),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Sending {
+    id: PacketId,
+    range: Range<usize>,
+    deadline: Option<Timestamp>,
+    timeout: Duration,
+    attempts: u32,
+    expects_ack: bool,
+}
+
+const QUEUE_CAPACITY: usize = 8;
+
+/// Packets waiting for the previous one to be acknowledged.
+#[derive(Debug)]
+struct Queue {
+    packets: [Option<(PacketId, PacketContent)>; QUEUE_CAPACITY],
+    start: usize,
+    len: usize,
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        Self {
+            packets: core::array::from_fn(|_| None),
+            start: 0,
+            len: 0,
+        }
+    }
+}
+
+impl Queue {
+    fn is_full(&self) -> bool {
+        self.len == QUEUE_CAPACITY
+    }
+
+    fn push(&mut self, id: PacketId, content: PacketContent) -> Result<()> {
+        if self.is_full() {
+            return Err(Error::QueueFull);
+        }
+
+        self.packets[(self.start + self.len) % QUEUE_CAPACITY] = Some((id, content));
+        self.len += 1;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<(PacketId, PacketContent)> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let packet = self.packets[self.start].take();
+        self.start = (self.start + 1) % QUEUE_CAPACITY;
+        self.len -= 1;
+        packet
+    }
 }
 
-const MESSAGE_HEADER: u8 = 0x3e;
-const MESSAGE_TRAILER: u8 = 0x3c;
+pub(crate) const MESSAGE_HEADER: u8 = 0x3e;
+pub(crate) const MESSAGE_TRAILER: u8 = 0x3c;
 const MESSAGE_ESCAPE: u8 = 0x3d;
 const MESSAGE_ESCAPE_MASK: u8 = 0b11101111;
 
+fn needs_escape(byte: u8) -> bool {
+    matches!(byte, MESSAGE_TRAILER | MESSAGE_ESCAPE | MESSAGE_HEADER)
+}
+
+/// Escape in place the first `len` bytes of `buf`, which must hold a whole frame.
+/// Returns the size of the escaped frame.
+pub(crate) fn escape_frame(buf: &mut [u8], len: usize) -> Result<usize> {
+    let escaped = buf[1..len - 1].iter().filter(|b| needs_escape(**b)).count();
+    if len + escaped > buf.len() {
+        return Err(Error::BufferTooSmall {
+            needed: len + escaped,
+            available: buf.len(),
+        });
+    }
+
+    // Walk backward so that no byte is overwritten before being moved.
+    let mut dst = len + escaped - 1;
+    buf[dst] = buf[len - 1];
+    for src in (1..len - 1).rev() {
+        let byte = buf[src];
+        if needs_escape(byte) {
+            buf[dst - 1] = byte & MESSAGE_ESCAPE_MASK;
+            buf[dst - 2] = MESSAGE_ESCAPE;
+            dst -= 2;
+        } else {
+            buf[dst - 1] = byte;
+            dst -= 1;
+        }
+    }
+
+    Ok(len + escaped)
+}
+
+/// Unescape a frame in place, returning its new size.
+fn unescape_frame(frame: &mut [u8]) -> usize {
+    let mut src = 0;
+    let mut dst = 0;
+    while src < frame.len() {
+        if frame[src] == MESSAGE_ESCAPE && src + 1 < frame.len() {
+            frame[dst] = frame[src + 1] | MESSAGE_ESCAPE_MASK.not();
+            src += 2;
+        } else {
+            frame[dst] = frame[src];
+            src += 1;
+        }
+        dst += 1;
+    }
+
+    dst
+}
+
+/// Initial size of the read and write buffers.
+const BUFFER_SIZE: usize = 1024;
+/// Size up to which the buffers grow to fit large frames, such as JSON payloads.
+#[cfg(feature = "alloc")]
+const MAX_BUFFER_SIZE: usize = 64 * 1024;
+
+#[cfg(feature = "alloc")]
+type Buffer = alloc::vec::Vec<u8>;
+#[cfg(not(feature = "alloc"))]
+type Buffer = [u8; BUFFER_SIZE];
+
+#[cfg(feature = "alloc")]
+fn new_buffer() -> Buffer {
+    alloc::vec![0u8; BUFFER_SIZE]
+}
+
+#[cfg(not(feature = "alloc"))]
+fn new_buffer() -> Buffer {
+    [0u8; BUFFER_SIZE]
+}
+
+/// Double the size of `buf`, returns `false` if it cannot grow any more.
+#[cfg(feature = "alloc")]
+fn grow_buffer(buf: &mut Buffer) -> bool {
+    let size = (buf.len() * 2).min(MAX_BUFFER_SIZE);
+    if size == buf.len() {
+        return false;
+    }
+
+    buf.resize(size, 0);
+    true
+}
+
+#[cfg(not(feature = "alloc"))]
+fn grow_buffer(_buf: &mut Buffer) -> bool {
+    false
+}
+
 #[derive(Debug)]
 pub struct Device {
     pending_packet: Option<Packet>,
-    read_buf: [u8; 1024],
-    write_buf: [u8; 1024],
+    read_buf: Buffer,
+    write_buf: Buffer,
     reading: Option<(usize, usize)>,
-    sending: Option<(Range<usize>, Option<Instant>, Duration)>,
+    sending: Option<Sending>,
+    failed_packet: Option<PacketId>,
+    queue: Queue,
+    next_id: u32,
+    retry_policy: RetryPolicy,
     seqnum: u8,
+    /// Sequence number of the last packet received from the device, used to detect
+    /// retransmissions when our ACK was lost.
+    last_received_seqnum: Option<u8>,
 }
 
 impl Default for Device {
     fn default() -> Self {
+        Self::new(RetryPolicy::default())
+    }
+}
+
+impl Device {
+    pub fn new(retry_policy: RetryPolicy) -> Self {
         Self {
             pending_packet: None,
-            read_buf: [0u8; 1024],
-            write_buf: [0u8; 1024],
+            read_buf: new_buffer(),
+            write_buf: new_buffer(),
             reading: None,
             sending: None,
+            failed_packet: None,
+            queue: Queue::default(),
+            next_id: 0,
+            retry_policy: retry_policy.clamped(),
             seqnum: 0,
+            last_received_seqnum: None,
         }
     }
-}
-
-const RETRY_DURATION: Duration = Duration::from_secs(1);
 
-impl Device {
+    /// Feed bytes received from the device.
+    ///
+    /// Returns the number of bytes consumed, which is less than `content.len()` when the
+    /// read buffer is full. In that case, call [`Device::poll`] until it is waiting and
+    /// feed the remaining bytes again.
     pub fn received_packet(&mut self, content: &[u8]) -> Result<usize> {
         trace!("received {:02x?}", content);
-        let (start, mut index) = self.reading.unwrap_or((0, 0));
-
-        let mut content_index = 0;
-        while content_index < content.len() && index < self.read_buf.len() - 1 {
-            if content[content_index] == MESSAGE_ESCAPE {
-                self.read_buf[index] = content[content_index + 1] | MESSAGE_ESCAPE_MASK.not();
-                content_index += 2;
-            } else {
-                self.read_buf[index] = content[content_index];
-                content_index += 1;
-            }
+        let (start, end) = self.reading.unwrap_or((0, 0));
 
-            index += 1;
-        }
+        // Move the incomplete frame to the front to make room for the new bytes.
+        self.read_buf.copy_within(start..end, 0);
+        let index = end - start;
 
-        self.reading = Some((start, index));
+        // Bytes are kept escaped until a whole frame is received, so that an escaped
+        // header or trailer is never mistaken for a frame boundary.
+        let size = content.len().min(self.read_buf.len() - index);
+        self.read_buf[index..index + size].copy_from_slice(&content[..size]);
 
-        return Ok(content_index);
+        self.reading = if index + size == 0 {
+            None
+        } else {
+            Some((0, index + size))
+        };
+
+        Ok(size)
     }
 
-    pub fn poll<'a>(&'a mut self) -> Result<State<'a>> {
-        if let Some(packet) = self.pending_packet.take() {
-            Ok(State::ReceivedPacket(packet))
-        } else if let Some((start, end)) = self.reading {
-            let pos = start
-                + self.read_buf[start..end]
+    /// Find the next complete frame in the read buffer, skipping anything that is not part
+    /// of a frame. Returns `None` if more data is needed.
+    fn next_frame(&mut self) -> Option<(usize, usize)> {
+        while let Some((start, end)) = self.reading {
+            let Some(trailer) = self.read_buf[start..end]
+                .iter()
+                .position(|c| *c == MESSAGE_TRAILER)
+            else {
+                let Some(header) = self.read_buf[start..end]
                     .iter()
-                    .position(|c| *c == MESSAGE_TRAILER)
-                    .unwrap()
-                + 1;
+                    .position(|c| *c == MESSAGE_HEADER)
+                else {
+                    warn!("skipping {} bytes without packet header", end - start);
+                    self.reading = None;
+                    return None;
+                };
 
+                if header > 0 {
+                    warn!("skipping {} bytes before packet header", header);
+                }
+
+                let start = start + header;
+                if end - start == self.read_buf.len() {
+                    if grow_buffer(&mut self.read_buf) {
+                        debug!("read buffer grown to {} bytes", self.read_buf.len());
+                        self.reading = Some((start, end));
+                        return None;
+                    }
+
+                    warn!("dropping frame larger than the read buffer");
+                    self.reading = Some((start + 1, end));
+                    continue;
+                }
+
+                self.reading = Some((start, end));
+                return None;
+            };
+
+            let pos = start + trailer + 1;
             self.reading = if pos == end { None } else { Some((pos, end)) };
-            let packet = v1::Packet::try_from(&self.read_buf[start..pos]);
+
+            // A frame starts at the last header before its trailer, anything before it
+            // is left over from a corrupted frame.
+            match self.read_buf[start..pos]
+                .iter()
+                .rposition(|c| *c == MESSAGE_HEADER)
+            {
+                Some(header) => {
+                    if header > 0 {
+                        warn!("skipping {} bytes before packet header", header);
+                    }
+                    return Some((start + header, pos));
+                }
+                None => warn!("skipping {} bytes without packet header", pos - start),
+            }
+        }
+
+        None
+    }
+
+    /// Advance the state of the device, `now` being used to schedule retransmissions.
+    pub fn poll<'a>(&'a mut self, now: Timestamp) -> Result<State<'a>> {
+        if let Some(packet) = self.pending_packet.take() {
+            return Ok(State::ReceivedPacket(packet));
+        }
+
+        // Dropped frames and ignored ACKs loop instead of recursing, so that a read buffer
+        // full of them cannot overflow the stack.
+        while let Some((start, end)) = self.next_frame() {
+            let len = unescape_frame(&mut self.read_buf[start..end]);
+            let packet = v1::Packet::try_from(&self.read_buf[start..start + len]);
 
             match packet {
+                Ok(packet) if packet.is_shot() => return Ok(State::ReceivedPacket(packet)),
                 Ok(packet) => {
+                    if let PacketContent::Raw { data_type, .. } = &packet.content {
+                        debug!("received packet with undecoded data type {:?}", data_type);
+                    }
+
                     if !packet.is_ack() {
                         let seqnum = packet.seqnum();
 
-                        self.pending_packet = Some(packet);
+                        if self.last_received_seqnum == Some(seqnum) {
+                            debug!("ignoring already received packet {:?}", packet);
+                        } else {
+                            self.last_received_seqnum = Some(seqnum);
+                            self.pending_packet = Some(packet);
+                        }
 
                         let size = self.encode_packet(PacketContent::Ack, Some(seqnum))?;
 
-                        Ok(State::SendPacket(&self.write_buf[size]))
-                    } else {
-                        if self.sending.is_some() {
-                            self.seqnum = packet.seqnum();
-                            self.sending = None;
-                        }
+                        return Ok(State::SendPacket(&self.write_buf[size], None));
+                    } else if let Some(sending) = self
+                        .sending
+                        .take_if(|_| packet.seqnum() == 1u8.wrapping_sub(self.seqnum))
+                    {
+                        self.seqnum = packet.seqnum();
 
-                        Ok(State::ReceivedPacket(packet))
+                        return Ok(State::PacketAcked(sending.id));
+                    } else {
+                        debug!("ignoring unexpected ack {:?}", packet);
                     }
                 }
 
-                Err(e) => {
-                    warn!("error while parsing packet : {}", e.error);
-                    let size = self.encode_packet(PacketContent::Ack, Some(e.seqnum))?;
+                Err(crate::TryFromPacketError {
+                    seqnum: Some(seqnum),
+                    error,
+                }) => {
+                    if self.last_received_seqnum != Some(seqnum) {
+                        warn!("error while parsing packet : {}", error);
+                        self.last_received_seqnum = Some(seqnum);
+                    }
 
-                    Ok(State::SendPacket(&self.write_buf[size]))
+                    let size = self.encode_packet(PacketContent::Ack, Some(seqnum))?;
+
+                    return Ok(State::SendPacket(&self.write_buf[size], None));
                 }
+
+                Err(crate::TryFromPacketError {
+                    seqnum: None,
+                    error,
+                }) => warn!("dropping invalid frame : {}", error),
             }
+        }
 
-            // TODO IGNORE SEQNUM ALREADY
-        } else if let Some((r, i, d)) = self.sending.take() {
-            if let Some(i) = i {
-                if i.elapsed() > RETRY_DURATION {
-                    self.sending = Some((r.clone(), Some(Instant::now() + d), d));
-                    Ok(State::SendPacket(&self.write_buf[r.clone()]))
-                } else {
-                    self.sending = Some((r, Some(i), d));
-                    Ok(State::WaitingPacket(Some(i.clone())))
+        if let Some(mut sending) = self.take_sending() {
+            match sending.deadline {
+                Some(_) if !sending.expects_ack => Ok(State::PacketAcked(sending.id)),
+                Some(deadline) if now < deadline => {
+                    self.sending = Some(sending);
+                    Ok(State::WaitingPacket(Some(deadline)))
+                }
+                Some(_) if sending.attempts >= self.retry_policy.max_attempts => {
+                    warn!(
+                        "no ack received after {} attempts for {:?}",
+                        sending.attempts, sending.id
+                    );
+                    Ok(State::PacketFailed(sending.id))
+                }
+                deadline => {
+                    if deadline.is_some() {
+                        sending.timeout = sending
+                            .timeout
+                            .checked_mul(self.retry_policy.backoff)
+                            .unwrap_or(sending.timeout);
+                    }
+
+                    sending.attempts += 1;
+                    sending.deadline = Some(now.saturating_add(sending.timeout));
+
+                    let (id, range) = (sending.id, sending.range.clone());
+                    self.sending = Some(sending);
+                    Ok(State::SendPacket(&self.write_buf[range], Some(id)))
                 }
-            } else {
-                self.sending = Some((r.clone(), Some(Instant::now() + d), d));
-                Ok(State::SendPacket(&self.write_buf[r.clone()]))
             }
+        } else if let Some(id) = self.failed_packet.take() {
+            Ok(State::PacketFailed(id))
         } else {
             Ok(State::WaitingPacket(None))
         }
     }
 
+    /// Take the packet being sent, or start sending the next queued one.
+    fn take_sending(&mut self) -> Option<Sending> {
+        while self.sending.is_none() {
+            let (id, content) = self.queue.pop()?;
+            let expects_ack = !content.data_type().is_shot();
+
+            match self.encode_packet(content, None) {
+                Ok(range) => {
+                    self.sending = Some(Sending {
+                        id,
+                        range,
+                        deadline: None,
+                        timeout: self.retry_policy.initial_timeout,
+                        attempts: 0,
+                        expects_ack,
+                    });
+                }
+                Err(e) => {
+                    warn!("failed to encode packet {:?} : {}", id, e);
+                    self.failed_packet = Some(id);
+                    return None;
+                }
+            }
+        }
+
+        self.sending.take()
+    }
+
     fn encode_packet(
         &mut self,
         command: PacketContent,
         seqnum: Option<u8>,
     ) -> Result<Range<usize>> {
         let seqnum = if command != PacketContent::Ack {
-            if self.sending.is_some() {
-                return Err(Error::PacketPending);
-            }
-
             seqnum.unwrap_or(self.seqnum)
         } else {
             1u8.wrapping_sub(seqnum.unwrap_or(self.seqnum))
@@ -153,19 +513,28 @@ impl Device {
 
         let packet = Packet::new(seqnum, command);
 
-        let start = self.sending.as_ref().map(|(c, _, _)| c.end).unwrap_or(0);
+        let start = self.sending.as_ref().map(|s| s.range.end).unwrap_or(0);
 
-        return Ok(start..start + packet.write_into(&mut self.write_buf[start..])?);
+        loop {
+            match packet.write_into(&mut self.write_buf[start..]) {
+                Err(Error::BufferTooSmall { .. }) if grow_buffer(&mut self.write_buf) => {
+                    debug!("write buffer grown to {} bytes", self.write_buf.len());
+                }
+                size => return Ok(start..start + size?),
+            }
+        }
     }
 
-    pub fn send_packet(&mut self, content: PacketContent) -> Result<()> {
+    /// Queue a packet, which is sent once the previous ones are acknowledged.
+    pub fn send_packet(&mut self, content: PacketContent) -> Result<PacketId> {
         trace!("send_packet : {:?}", content);
-        if self.sending.is_some() {
-            return Err(Error::PacketPending);
-        } else {
-            let seq = self.encode_packet(content, None)?;
-            self.sending = Some((seq, None, RETRY_DURATION));
-            Ok(())
-        }
+        let id = PacketId(self.next_id);
+        self.queue.push(id, content)?;
+        self.next_id = self.next_id.wrapping_add(1);
+        Ok(id)
+    }
+
+    pub fn is_queue_full(&self) -> bool {
+        self.queue.is_full()
     }
 }
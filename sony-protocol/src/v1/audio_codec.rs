@@ -0,0 +1,82 @@
+use super::Payload;
+
+/// Inquired type of the audio codec commands.
+const AUDIO_CODEC_TYPE: u8 = 0x00;
+
+/// Codec negotiated with the source for the current A2DP stream.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AudioCodec {
+    /// No stream is established yet.
+    Unsettled,
+    Sbc,
+    Aac,
+    Ldac,
+    AptX,
+    AptXHd,
+    Lc3,
+    Other,
+    /// Codec byte not listed above, kept so that a codec change is never dropped.
+    Unknown(u8),
+}
+
+impl From<u8> for AudioCodec {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => Self::Unsettled,
+            0x01 => Self::Sbc,
+            0x02 => Self::Aac,
+            0x10 => Self::Ldac,
+            0x20 => Self::AptX,
+            0x21 => Self::AptXHd,
+            0x30 => Self::Lc3,
+            0xff => Self::Other,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<AudioCodec> for u8 {
+    fn from(value: AudioCodec) -> Self {
+        match value {
+            AudioCodec::Unsettled => 0x00,
+            AudioCodec::Sbc => 0x01,
+            AudioCodec::Aac => 0x02,
+            AudioCodec::Ldac => 0x10,
+            AudioCodec::AptX => 0x20,
+            AudioCodec::AptXHd => 0x21,
+            AudioCodec::Lc3 => 0x30,
+            AudioCodec::Other => 0xff,
+            AudioCodec::Unknown(value) => value,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for AudioCodec {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let [_, codec] = value
+            .get(..2)
+            .ok_or(crate::Error::MissingBytes)?
+            .try_into()?;
+
+        Ok(Self::from(codec))
+    }
+}
+
+impl Payload for AudioCodec {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [AUDIO_CODEC_TYPE, u8::from(*self)]
+            .as_slice()
+            .write_into(buf)
+    }
+}
+
+#[derive(Debug)]
+pub struct GetAudioCodec;
+
+impl Payload for GetAudioCodec {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [AUDIO_CODEC_TYPE].as_slice().write_into(buf)
+    }
+}
@@ -0,0 +1,204 @@
+use super::{speak_to_chat::SPEAK_TO_CHAT_TYPE, Payload};
+
+/// Setting read or written by the automatic power off and button mode commands.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum AutoPowerOffButtonType {
+    NcAmbButton = 0x03,
+    ButtonModes = 0x06,
+    AutoPowerOff = 0x04,
+    SpeakToChat = SPEAK_TO_CHAT_TYPE,
+}
+
+impl TryFrom<u8> for AutoPowerOffButtonType {
+    type Error = crate::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x03 => Ok(Self::NcAmbButton),
+            0x04 => Ok(Self::AutoPowerOff),
+            0x06 => Ok(Self::ButtonModes),
+            SPEAK_TO_CHAT_TYPE => Ok(Self::SpeakToChat),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "auto power off or button type",
+                value,
+            }),
+        }
+    }
+}
+
+impl Payload for AutoPowerOffButtonType {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [*self as u8].as_slice().write_into(buf)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AutoPowerOff {
+    Off,
+    After5Minutes,
+    After30Minutes,
+    After1Hour,
+    After3Hours,
+    WhenTakenOff,
+}
+
+impl AutoPowerOff {
+    fn codes(self) -> [u8; 2] {
+        match self {
+            Self::Off => [0x11, 0x00],
+            Self::After5Minutes => [0x00, 0x00],
+            Self::After30Minutes => [0x01, 0x01],
+            Self::After1Hour => [0x02, 0x02],
+            Self::After3Hours => [0x03, 0x03],
+            Self::WhenTakenOff => [0x10, 0x00],
+        }
+    }
+}
+
+impl TryFrom<[u8; 2]> for AutoPowerOff {
+    type Error = crate::Error;
+
+    fn try_from(value: [u8; 2]) -> Result<Self, Self::Error> {
+        // The second code only repeats the first one for the timeouts.
+        match value[0] {
+            0x11 => Ok(Self::Off),
+            0x00 => Ok(Self::After5Minutes),
+            0x01 => Ok(Self::After30Minutes),
+            0x02 => Ok(Self::After1Hour),
+            0x03 => Ok(Self::After3Hours),
+            0x10 => Ok(Self::WhenTakenOff),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "auto power off",
+                value,
+            }),
+        }
+    }
+}
+
+/// Function assigned to a button with [`AutoPowerOffButtonPayload::ButtonModes`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum ButtonFunction {
+    AmbientSoundControl = 0x00,
+    VoiceAssistant = 0x10,
+    PlaybackControl = 0x20,
+    Off = 0xff,
+}
+
+impl TryFrom<u8> for ButtonFunction {
+    type Error = crate::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Self::AmbientSoundControl),
+            0x10 => Ok(Self::VoiceAssistant),
+            0x20 => Ok(Self::PlaybackControl),
+            0xff => Ok(Self::Off),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "button function",
+                value,
+            }),
+        }
+    }
+}
+
+/// Ambient sound modes the NC/AMB button cycles through.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum NcAmbButtonMode {
+    NoiseCancellingAmbientSoundOff = 0x01,
+    NoiseCancellingAmbientSound = 0x02,
+    NoiseCancellingOff = 0x03,
+    AmbientSoundOff = 0x04,
+}
+
+impl TryFrom<u8> for NcAmbButtonMode {
+    type Error = crate::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(Self::NoiseCancellingAmbientSoundOff),
+            0x02 => Ok(Self::NoiseCancellingAmbientSound),
+            0x03 => Ok(Self::NoiseCancellingOff),
+            0x04 => Ok(Self::AmbientSoundOff),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "NC/AMB button mode",
+                value,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AutoPowerOffButtonPayload {
+    AutoPowerOff(AutoPowerOff),
+    NcAmbButton(NcAmbButtonMode),
+    /// Functions of the left and right buttons or touch panels.
+    ButtonModes {
+        left: ButtonFunction,
+        right: ButtonFunction,
+    },
+    SpeakToChat {
+        enabled: bool,
+    },
+}
+
+impl AutoPowerOffButtonPayload {
+    pub fn setting_type(&self) -> AutoPowerOffButtonType {
+        match self {
+            Self::AutoPowerOff(_) => AutoPowerOffButtonType::AutoPowerOff,
+            Self::NcAmbButton(_) => AutoPowerOffButtonType::NcAmbButton,
+            Self::ButtonModes { .. } => AutoPowerOffButtonType::ButtonModes,
+            Self::SpeakToChat { .. } => AutoPowerOffButtonType::SpeakToChat,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for AutoPowerOffButtonPayload {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (&setting_type, value) = value.split_first().ok_or(crate::Error::MissingBytes)?;
+
+        match (AutoPowerOffButtonType::try_from(setting_type)?, value) {
+            (AutoPowerOffButtonType::AutoPowerOff, [_, a, b, ..]) => {
+                Ok(Self::AutoPowerOff(AutoPowerOff::try_from([*a, *b])?))
+            }
+            (AutoPowerOffButtonType::NcAmbButton, [_, _, _, _, mode, ..]) => {
+                Ok(Self::NcAmbButton(NcAmbButtonMode::try_from(*mode)?))
+            }
+            (AutoPowerOffButtonType::ButtonModes, [_, left, right, ..]) => Ok(Self::ButtonModes {
+                left: ButtonFunction::try_from(*left)?,
+                right: ButtonFunction::try_from(*right)?,
+            }),
+            // The headset uses 0x00 for enabled
+            (AutoPowerOffButtonType::SpeakToChat, [enabled, ..]) => Ok(Self::SpeakToChat {
+                enabled: *enabled == 0x00,
+            }),
+            _ => Err(crate::Error::MissingBytes),
+        }
+    }
+}
+
+impl Payload for AutoPowerOffButtonPayload {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        let setting_type = self.setting_type() as u8;
+        match self {
+            Self::AutoPowerOff(auto_power_off) => {
+                let [a, b] = auto_power_off.codes();
+                [setting_type, 0x01, a, b].as_slice().write_into(buf)
+            }
+            // The bytes before the mode are sent as is, their meaning is unknown.
+            Self::NcAmbButton(mode) => [setting_type, 0x01, 0x35, 0x01, 0x00, *mode as u8]
+                .as_slice()
+                .write_into(buf),
+            Self::ButtonModes { left, right } => [setting_type, 0x02, *left as u8, *right as u8]
+                .as_slice()
+                .write_into(buf),
+            Self::SpeakToChat { enabled } => [setting_type, !*enabled as u8, 0x01]
+                .as_slice()
+                .write_into(buf),
+        }
+    }
+}
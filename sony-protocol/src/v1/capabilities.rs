@@ -0,0 +1,125 @@
+use super::{ensure_capacity, Payload};
+
+/// Reply to [`super::PayloadCommand1::InitRequest`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ProtocolInfo {
+    pub version: u32,
+}
+
+impl TryFrom<&[u8]> for ProtocolInfo {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        // The first byte is the inquired type, always 0x00. The support tables that may
+        // follow the version are superseded by the support function list. Some models
+        // send a shorter reply without the version, which is then reported as 0.
+        let version = match value.get(1..5) {
+            Some(version) => u32::from_be_bytes(version.try_into()?),
+            None => 0,
+        };
+
+        Ok(Self { version })
+    }
+}
+
+impl Payload for ProtocolInfo {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        let [a, b, c, d] = self.version.to_be_bytes();
+        [0x00, a, b, c, d].as_slice().write_into(buf)
+    }
+}
+
+/// Function types reported in the support function list, numbered as in Sony's function
+/// type table. Only the types checked against that table are named, the others are
+/// available as raw values from [`SupportFunctions`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum Feature {
+    PowerOff = 0x21,
+    Equalizer = 0x51,
+    NoiseCancelling = 0x61,
+    /// Noise cancelling and ambient sound, switched together with
+    /// [`super::PayloadCommand1::AmbientSoundControlSet`].
+    NoiseCancellingAndAmbientSound = 0x62,
+    /// Ambient sound without noise cancelling.
+    AmbientSound = 0x63,
+    NcOptimizer = 0x81,
+}
+
+/// Set of the function types supported by the headset.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct SupportFunctions([u64; 4]);
+
+impl SupportFunctions {
+    pub fn insert(&mut self, function_type: u8) {
+        self.0[function_type as usize / 64] |= 1 << (function_type % 64);
+    }
+
+    pub fn contains(&self, function_type: u8) -> bool {
+        self.0[function_type as usize / 64] & (1 << (function_type % 64)) != 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=u8::MAX).filter(|f| self.contains(*f))
+    }
+}
+
+impl TryFrom<&[u8]> for SupportFunctions {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        // Inquired type, then the number of functions and a function type and a
+        // priority for each.
+        let count = *value.get(1).ok_or(crate::Error::MissingBytes)? as usize;
+        let functions = value
+            .get(2..2 + count * 2)
+            .ok_or(crate::Error::MissingBytes)?;
+
+        let mut support = Self::default();
+        for function in functions.chunks_exact(2) {
+            support.insert(function[0]);
+        }
+        Ok(support)
+    }
+}
+
+impl Payload for SupportFunctions {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        let count = self.iter().count();
+        let needed = 2 + count * 2;
+        ensure_capacity(buf, needed)?;
+
+        buf[0] = 0x00;
+        buf[1] = count as u8;
+        for (i, function) in self.iter().enumerate() {
+            buf[2 + i * 2] = function;
+            buf[3 + i * 2] = 0x00;
+        }
+        Ok(needed as u32)
+    }
+}
+
+/// What the connected headset supports, gathered while initializing the connection.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Capabilities {
+    pub protocol_version: u32,
+    pub functions: SupportFunctions,
+}
+
+impl Capabilities {
+    pub fn new(protocol: ProtocolInfo, functions: SupportFunctions) -> Self {
+        Self {
+            protocol_version: protocol.version,
+            functions,
+        }
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.functions.contains(feature as u8)
+    }
+
+    /// Whether noise cancelling and ambient sound control are supported.
+    pub fn supports_anc(&self) -> bool {
+        self.supports(Feature::NoiseCancellingAndAmbientSound)
+    }
+}
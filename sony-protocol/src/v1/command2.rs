@@ -0,0 +1,94 @@
+use super::{write_command, Payload, RawPayload};
+
+/// Payloads of the second command table (`DATA_MDR_NO2`).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PayloadCommand2 {
+    VoiceGuidanceGet,
+    VoiceGuidanceRet(VoiceGuidance),
+    VoiceGuidanceSet(VoiceGuidance),
+    VoiceGuidanceNotify(VoiceGuidance),
+
+    /// Command not decoded yet, `payload` does not include the opcode.
+    Unknown {
+        opcode: u8,
+        payload: RawPayload,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct VoiceGuidance {
+    pub enabled: bool,
+}
+
+impl<'a> TryFrom<&'a [u8]> for PayloadCommand2 {
+    type Error = crate::Error;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let (&opcode, payload) = value.split_first().ok_or(crate::Error::MissingBytes)?;
+
+        match opcode {
+            0x46 => Ok(Self::VoiceGuidanceGet),
+            0x47 => Ok(Self::VoiceGuidanceRet(VoiceGuidance::try_from(payload)?)),
+            0x48 => Ok(Self::VoiceGuidanceSet(VoiceGuidance::try_from(payload)?)),
+            0x49 => Ok(Self::VoiceGuidanceNotify(VoiceGuidance::try_from(payload)?)),
+            opcode => Ok(Self::Unknown {
+                opcode,
+                payload: RawPayload::try_from(payload)?,
+            }),
+        }
+    }
+}
+
+impl Payload for PayloadCommand2 {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        let (opcode, payload): (u8, &dyn Payload) = match self {
+            Self::VoiceGuidanceGet => (0x46, &GetVoiceGuidance),
+            Self::VoiceGuidanceRet(v) => (0x47, v),
+            Self::VoiceGuidanceSet(v) => (0x48, v),
+            Self::VoiceGuidanceNotify(v) => (0x49, v),
+            Self::Unknown { opcode, payload } => (*opcode, payload),
+        };
+
+        write_command(buf, opcode, payload)
+    }
+}
+
+struct GetVoiceGuidance;
+
+impl Payload for GetVoiceGuidance {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [0x01, 0x01].as_slice().write_into(buf)
+    }
+}
+
+impl Payload for VoiceGuidance {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        // The headset uses 0x00 for enabled
+        [0x01, 0x01, if self.enabled { 0x00 } else { 0x01 }]
+            .as_slice()
+            .write_into(buf)
+    }
+}
+
+impl TryFrom<&[u8]> for VoiceGuidance {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() < 3 {
+            return Err(crate::Error::MissingBytes);
+        }
+
+        let enabled = match value[2] {
+            0x00 => true,
+            0x01 => false,
+            value => {
+                return Err(crate::Error::InvalidValueForEnum {
+                    what: "voice guidance",
+                    value,
+                })
+            }
+        };
+
+        Ok(Self { enabled })
+    }
+}
@@ -0,0 +1,154 @@
+use core::fmt::Debug;
+
+use super::{write_command, Payload};
+
+/// Information that can be requested with [`super::PayloadCommand1::DeviceInfoRequest`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum DeviceInfoType {
+    ModelName = 0x01,
+    FirmwareVersion = 0x02,
+    SeriesAndColor = 0x03,
+}
+
+impl TryFrom<u8> for DeviceInfoType {
+    type Error = crate::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(Self::ModelName),
+            0x02 => Ok(Self::FirmwareVersion),
+            0x03 => Ok(Self::SeriesAndColor),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "device info type",
+                value,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DeviceInfo {
+    ModelName(InfoString),
+    FirmwareVersion(InfoString),
+    /// The series and color codes are reported as is.
+    SeriesAndColor {
+        series: u8,
+        color: u8,
+    },
+}
+
+impl DeviceInfo {
+    pub fn info_type(&self) -> DeviceInfoType {
+        match self {
+            Self::ModelName(_) => DeviceInfoType::ModelName,
+            Self::FirmwareVersion(_) => DeviceInfoType::FirmwareVersion,
+            Self::SeriesAndColor { .. } => DeviceInfoType::SeriesAndColor,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for DeviceInfo {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (&info_type, payload) = value.split_first().ok_or(crate::Error::MissingBytes)?;
+
+        match DeviceInfoType::try_from(info_type)? {
+            DeviceInfoType::ModelName => Ok(Self::ModelName(InfoString::try_from(payload)?)),
+            DeviceInfoType::FirmwareVersion => {
+                Ok(Self::FirmwareVersion(InfoString::try_from(payload)?))
+            }
+            DeviceInfoType::SeriesAndColor => match payload {
+                [series, color, ..] => Ok(Self::SeriesAndColor {
+                    series: *series,
+                    color: *color,
+                }),
+                _ => Err(crate::Error::MissingBytes),
+            },
+        }
+    }
+}
+
+impl Payload for DeviceInfoType {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [*self as u8].as_slice().write_into(buf)
+    }
+}
+
+impl Payload for DeviceInfo {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        match self {
+            Self::ModelName(s) | Self::FirmwareVersion(s) => {
+                write_command(buf, self.info_type() as u8, s)
+            }
+            Self::SeriesAndColor { series, color } => [self.info_type() as u8, *series, *color]
+                .as_slice()
+                .write_into(buf),
+        }
+    }
+}
+
+/// UTF-8 string prefixed by its length, as sent by the headset.
+#[derive(Clone, PartialEq, Eq)]
+pub struct InfoString {
+    len: usize,
+    data: [u8; InfoString::CAPACITY],
+}
+
+impl InfoString {
+    /// Longest string the headset can send, its length being sent on one byte.
+    pub const CAPACITY: usize = u8::MAX as usize;
+
+    pub fn as_str(&self) -> &str {
+        // Only valid UTF-8 is stored.
+        core::str::from_utf8(&self.data[..self.len]).unwrap_or_default()
+    }
+}
+
+impl Debug for InfoString {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl TryFrom<&str> for InfoString {
+    type Error = crate::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let bytes = value.as_bytes();
+        if bytes.len() > Self::CAPACITY {
+            return Err(crate::Error::BufferTooSmall {
+                needed: bytes.len(),
+                available: Self::CAPACITY,
+            });
+        }
+
+        let mut data = [0u8; Self::CAPACITY];
+        data[..bytes.len()].copy_from_slice(bytes);
+        Ok(Self {
+            len: bytes.len(),
+            data,
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for InfoString {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (&len, string) = value.split_first().ok_or(crate::Error::MissingBytes)?;
+        let string = string
+            .get(..len as usize)
+            .ok_or(crate::Error::MissingBytes)?;
+        let string = core::str::from_utf8(string).map_err(|_| crate::Error::InvalidString)?;
+
+        Self::try_from(string)
+    }
+}
+
+impl Payload for InfoString {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        write_command(buf, self.len as u8, &self.data[..self.len].as_ref())
+    }
+}
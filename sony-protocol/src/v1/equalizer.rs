@@ -0,0 +1,161 @@
+use super::{ensure_capacity, Payload};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum EqPreset {
+    Off = 0x00,
+    Bright = 0x10,
+    Excited = 0x11,
+    Mellow = 0x12,
+    Relaxed = 0x13,
+    Vocal = 0x14,
+    TrebleBoost = 0x15,
+    BassBoost = 0x16,
+    Speech = 0x17,
+    Manual = 0xa0,
+    Custom1 = 0xa1,
+    Custom2 = 0xa2,
+}
+
+impl TryFrom<u8> for EqPreset {
+    type Error = crate::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Self::Off),
+            0x10 => Ok(Self::Bright),
+            0x11 => Ok(Self::Excited),
+            0x12 => Ok(Self::Mellow),
+            0x13 => Ok(Self::Relaxed),
+            0x14 => Ok(Self::Vocal),
+            0x15 => Ok(Self::TrebleBoost),
+            0x16 => Ok(Self::BassBoost),
+            0x17 => Ok(Self::Speech),
+            0xa0 => Ok(Self::Manual),
+            0xa1 => Ok(Self::Custom1),
+            0xa2 => Ok(Self::Custom2),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "eq preset",
+                value,
+            }),
+        }
+    }
+}
+
+/// Levels of the custom curve, each in [`EqBands::MIN`]..=[`EqBands::MAX`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct EqBands {
+    pub clear_bass: i8,
+    /// From 400Hz to 16kHz.
+    pub bands: [i8; 5],
+}
+
+impl EqBands {
+    pub const MIN: i8 = -10;
+    pub const MAX: i8 = 10;
+
+    /// Levels are sent with this offset so that they are positive.
+    const OFFSET: i8 = 10;
+
+    fn check(&self) -> crate::Result<()> {
+        for level in core::iter::once(self.clear_bass).chain(self.bands) {
+            if !(Self::MIN..=Self::MAX).contains(&level) {
+                return Err(crate::Error::OutOfRange {
+                    what: "eq band level",
+                    value: level.into(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&[u8]> for EqBands {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let [clear_bass, bands @ ..]: [u8; 6] = value
+            .get(..6)
+            .ok_or(crate::Error::MissingBytes)?
+            .try_into()?;
+
+        let level = |raw: u8| {
+            i8::try_from(raw)
+                .map(|l| l - Self::OFFSET)
+                .map_err(|_| crate::Error::OutOfRange {
+                    what: "eq band level",
+                    value: raw.into(),
+                })
+        };
+
+        let eq_bands = Self {
+            clear_bass: level(clear_bass)?,
+            bands: [
+                level(bands[0])?,
+                level(bands[1])?,
+                level(bands[2])?,
+                level(bands[3])?,
+                level(bands[4])?,
+            ],
+        };
+        eq_bands.check()?;
+        Ok(eq_bands)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct EqualizerPayload {
+    pub preset: EqPreset,
+    /// Custom curve, `None` when the headset does not send it along the preset.
+    pub bands: Option<EqBands>,
+}
+
+impl TryFrom<&[u8]> for EqualizerPayload {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        // Inquired type, preset and number of levels that follow.
+        let [_, preset, count] = value
+            .get(..3)
+            .ok_or(crate::Error::MissingBytes)?
+            .try_into()?;
+
+        let bands = match count {
+            0 => None,
+            _ => Some(EqBands::try_from(&value[3..])?),
+        };
+
+        Ok(Self {
+            preset: EqPreset::try_from(preset)?,
+            bands,
+        })
+    }
+}
+
+impl Payload for EqualizerPayload {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        match &self.bands {
+            None => [0x01, self.preset as u8, 0x00].as_slice().write_into(buf),
+            Some(bands) => {
+                bands.check()?;
+                ensure_capacity(buf, 9)?;
+
+                buf[..3].copy_from_slice(&[0x01, self.preset as u8, 0x06]);
+                buf[3] = (bands.clear_bass + EqBands::OFFSET) as u8;
+                for (dst, level) in buf[4..9].iter_mut().zip(bands.bands) {
+                    *dst = (level + EqBands::OFFSET) as u8;
+                }
+                Ok(9)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GetEqualizer;
+
+impl Payload for GetEqualizer {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [0x01].as_slice().write_into(buf)
+    }
+}
@@ -0,0 +1,38 @@
+use alloc::string::String;
+
+use super::{write_command, Payload};
+
+/// JSON text exchanged by newer firmwares, kept as received since its content depends on
+/// the model and the requested type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct JsonPayload {
+    pub json_type: u8,
+    pub text: String,
+}
+
+impl JsonPayload {
+    #[cfg(feature = "serde_json")]
+    pub fn parse(&self) -> serde_json::Result<serde_json::Value> {
+        serde_json::from_str(&self.text)
+    }
+}
+
+impl TryFrom<&[u8]> for JsonPayload {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (&json_type, text) = value.split_first().ok_or(crate::Error::MissingBytes)?;
+        let text = core::str::from_utf8(text).map_err(|_| crate::Error::InvalidString)?;
+
+        Ok(Self {
+            json_type,
+            text: text.into(),
+        })
+    }
+}
+
+impl Payload for JsonPayload {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        write_command(buf, self.json_type, &self.text.as_bytes())
+    }
+}
@@ -1,4 +1,39 @@
-use std::array::TryFromSliceError;
+use core::fmt::Debug;
+
+use crate::{MESSAGE_HEADER, MESSAGE_TRAILER};
+
+mod audio_codec;
+mod auto_power_off;
+mod capabilities;
+mod command2;
+mod device_info;
+mod equalizer;
+#[cfg(feature = "alloc")]
+mod json;
+mod nc_optimizer;
+mod sound_position;
+mod speak_to_chat;
+mod touch_sensor;
+mod upsampling;
+mod volume;
+
+pub use audio_codec::AudioCodec;
+pub use auto_power_off::{
+    AutoPowerOff, AutoPowerOffButtonPayload, AutoPowerOffButtonType, ButtonFunction,
+    NcAmbButtonMode,
+};
+pub use capabilities::{Capabilities, Feature, ProtocolInfo, SupportFunctions};
+pub use command2::{PayloadCommand2, VoiceGuidance};
+pub use device_info::{DeviceInfo, DeviceInfoType, InfoString};
+pub use equalizer::{EqBands, EqPreset, EqualizerPayload};
+#[cfg(feature = "alloc")]
+pub use json::JsonPayload;
+pub use nc_optimizer::{NcOptimizerStart, NcOptimizerState, NcOptimizerStatus};
+pub use sound_position::{SoundPosition, SoundPositionPayload, SoundPositionType, SurroundMode};
+pub use speak_to_chat::{SpeakToChatConfig, SpeakToChatSensitivity, SpeakToChatTimeout};
+pub use touch_sensor::{TouchSensorPayload, TouchSensorType};
+pub use upsampling::{UpsamplingMode, UpsamplingPayload};
+pub use volume::VolumePayload;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[repr(u8)]
@@ -29,7 +64,16 @@ impl TryFrom<&[u8]> for BatteryState {
     type Error = crate::Error;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let battery_type = BatteryType::try_from(value[0])?;
+        let battery_type =
+            BatteryType::try_from(*value.first().ok_or(crate::Error::MissingBytes)?)?;
+        let len = match battery_type {
+            BatteryType::Single | BatteryType::Case => 3,
+            BatteryType::Dual => 5,
+        };
+        if value.len() < len {
+            return Err(crate::Error::MissingBytes);
+        }
+
         match battery_type {
             BatteryType::Single => {
                 let level = value[1];
@@ -87,11 +131,118 @@ pub struct Packet {
     pub content: PacketContent,
 }
 
+/// Data type of a packet, second byte of the frame.
+///
+/// "Shot" data types are not acknowledged by the receiver.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DataType {
+    Data,
+    Ack,
+    DataMcNo1,
+    DataIcd,
+    DataEv,
+    DataMdr,
+    DataCommon,
+    DataMdrNo2,
+    Shot,
+    ShotMcNo1,
+    ShotIcd,
+    ShotEv,
+    ShotMdr,
+    ShotCommon,
+    ShotMdrNo2,
+    LargeDataCommon,
+    Unknown(u8),
+}
+
+impl DataType {
+    pub fn is_shot(self) -> bool {
+        matches!(
+            self,
+            Self::Shot
+                | Self::ShotMcNo1
+                | Self::ShotIcd
+                | Self::ShotEv
+                | Self::ShotMdr
+                | Self::ShotCommon
+                | Self::ShotMdrNo2
+        )
+    }
+}
+
+impl From<u8> for DataType {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => Self::Data,
+            0x01 => Self::Ack,
+            0x02 => Self::DataMcNo1,
+            0x09 => Self::DataIcd,
+            0x0a => Self::DataEv,
+            0x0c => Self::DataMdr,
+            0x0d => Self::DataCommon,
+            0x0e => Self::DataMdrNo2,
+            0x10 => Self::Shot,
+            0x12 => Self::ShotMcNo1,
+            0x19 => Self::ShotIcd,
+            0x1a => Self::ShotEv,
+            0x1c => Self::ShotMdr,
+            0x1d => Self::ShotCommon,
+            0x1e => Self::ShotMdrNo2,
+            0x2d => Self::LargeDataCommon,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<DataType> for u8 {
+    fn from(value: DataType) -> Self {
+        match value {
+            DataType::Data => 0x00,
+            DataType::Ack => 0x01,
+            DataType::DataMcNo1 => 0x02,
+            DataType::DataIcd => 0x09,
+            DataType::DataEv => 0x0a,
+            DataType::DataMdr => 0x0c,
+            DataType::DataCommon => 0x0d,
+            DataType::DataMdrNo2 => 0x0e,
+            DataType::Shot => 0x10,
+            DataType::ShotMcNo1 => 0x12,
+            DataType::ShotIcd => 0x19,
+            DataType::ShotEv => 0x1a,
+            DataType::ShotMdr => 0x1c,
+            DataType::ShotCommon => 0x1d,
+            DataType::ShotMdrNo2 => 0x1e,
+            DataType::LargeDataCommon => 0x2d,
+            DataType::Unknown(value) => value,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum PacketContent {
     Ack,
     Command1(PayloadCommand1),
-    Command2,
+    Command2(PayloadCommand2),
+    ShotCommand1(PayloadCommand1),
+    ShotCommand2(PayloadCommand2),
+    /// Packet of a data type whose payload is not decoded.
+    Raw {
+        data_type: DataType,
+        payload: RawPayload,
+    },
+}
+
+impl PacketContent {
+    pub fn data_type(&self) -> DataType {
+        match self {
+            Self::Ack => DataType::Ack,
+            Self::Command1(_) => DataType::DataMdr,
+            Self::Command2(_) => DataType::DataMdrNo2,
+            Self::ShotCommand1(_) => DataType::ShotMdr,
+            Self::ShotCommand2(_) => DataType::ShotMdrNo2,
+            Self::Raw { data_type, .. } => *data_type,
+        }
+    }
 }
 
 impl Packet {
@@ -103,75 +254,79 @@ impl Packet {
         self.seqnum
     }
 
-    pub fn write_into(self, buf: &mut [u8]) -> crate::Result<usize> {
-        buf[0] = 0x3e;
-        buf[1] = match self.content {
-            PacketContent::Ack => 0x01,
-            PacketContent::Command1(_) => 0x0c,
-            PacketContent::Command2 => 0xe,
-        };
+    pub fn write_into(&self, buf: &mut [u8]) -> crate::Result<usize> {
+        // Header, data type, seqnum, size, checksum and trailer
+        ensure_capacity(buf, 9)?;
+
+        buf[0] = MESSAGE_HEADER;
+        buf[1] = self.content.data_type().into();
 
         buf[2] = self.seqnum();
         let size = self.write_payload(&mut buf[7..])?;
         buf[3..7].copy_from_slice(&size.to_be_bytes());
 
         let end = 7 + size as usize;
+        ensure_capacity(buf, end + 2)?;
 
-        let checksum = buf[1..end]
-            .iter()
-            .fold(0, |acc: u8, x: &u8| acc.wrapping_add(*x));
+        buf[end] = checksum(&buf[1..end]);
 
-        buf[end] = checksum;
+        buf[end + 1] = MESSAGE_TRAILER;
 
-        buf[end + 1] = 60;
-
-        Ok(end + 2)
+        crate::escape_frame(buf, end + 2)
     }
 
     pub fn is_ack(&self) -> bool {
         PacketContent::Ack == self.content
     }
 
+    /// Whether the packet is a "shot", which is not acknowledged.
+    pub fn is_shot(&self) -> bool {
+        self.content.data_type().is_shot()
+    }
+
     fn write_payload(&self, buf: &mut [u8]) -> crate::Result<u32> {
         match &self.content {
             PacketContent::Ack => Ok(0),
-            PacketContent::Command1(p) => p.write_into(buf),
-            PacketContent::Command2 => todo!(),
+            PacketContent::Command1(p) | PacketContent::ShotCommand1(p) => p.write_into(buf),
+            PacketContent::Command2(p) | PacketContent::ShotCommand2(p) => p.write_into(buf),
+            PacketContent::Raw { payload, .. } => payload.write_into(buf),
         }
     }
 }
 
+// Payloads are stored inline so that decoding needs no allocator.
 #[derive(Debug, PartialEq, Eq, Clone)]
-#[allow(dead_code)]
+#[allow(dead_code, clippy::large_enum_variant)]
 pub enum PayloadCommand1 {
     InitRequest,
-    InitReply([u8; 3]),
+    InitReply(ProtocolInfo),
 
-    FwVersionRequest,
-    FwVersionReply,
+    DeviceInfoRequest(DeviceInfoType),
+    DeviceInfoReply(DeviceInfo),
 
     Init2Request,
-    Init2Reply,
+    Init2Reply(SupportFunctions),
 
     BatteryLevelRequest(BatteryType),
     BatteryLevelReply(BatteryState),
     BatteryLevelNotify(BatteryState),
 
     AudioCodecRequest,
-    AudioCodecReply,
-    AudioCodecNotify,
+    AudioCodecReply(AudioCodec),
+    AudioCodecNotify(AudioCodec),
 
+    /// Turns the headset off, it closes the connection right after.
     PowerOff,
 
-    SoundPositionOrModeGet,
-    SoundPositionOrModeRet,
-    SoundPositionOrModeSet,
-    SoundPositionOrModeNotify,
+    SoundPositionOrModeGet(SoundPositionType),
+    SoundPositionOrModeRet(SoundPositionPayload),
+    SoundPositionOrModeSet(SoundPositionPayload),
+    SoundPositionOrModeNotify(SoundPositionPayload),
 
     EqualizerGet,
-    EqualizerRet,
-    EqualizerSet,
-    EqualizerNotify,
+    EqualizerRet(EqualizerPayload),
+    EqualizerSet(EqualizerPayload),
+    EqualizerNotify(EqualizerPayload),
 
     AmbientSoundControlGet,
     AmbientSoundControlRet(AncPayload),
@@ -179,39 +334,41 @@ pub enum PayloadCommand1 {
     AmbientSoundControlNotify(AncPayload),
 
     VolumeGet,
-    VolumeRet,
-    VolumeSet,
-    VolumeNotify,
+    VolumeRet(VolumePayload),
+    VolumeSet(VolumePayload),
+    VolumeNotify(VolumePayload),
 
-    NoiseCancellingOptimizerStart,
-    NoiseCancellingOptimizerStatus,
+    NoiseCancellingOptimizerStart(NcOptimizerStart),
+    NoiseCancellingOptimizerStatus(NcOptimizerStatus),
 
     NoiseCancellingOptimizerStateGet,
-    NoiseCancellingOptimizerStateRet,
-    NoiseCancellingOptimizerStateNotify,
+    NoiseCancellingOptimizerStateRet(NcOptimizerState),
+    NoiseCancellingOptimizerStateNotify(NcOptimizerState),
 
-    TouchSensorGet,
-    TouchSensorRet,
-    TouchSensorSet,
-    TouchSensorNotify,
+    TouchSensorGet(TouchSensorType),
+    TouchSensorRet(TouchSensorPayload),
+    TouchSensorSet(TouchSensorPayload),
+    TouchSensorNotify(TouchSensorPayload),
 
     AudioUpsamplingGet,
-    AudioUpsamplingRet,
-    AudioUpsamplingSet,
-    AudioUpsamplingNotify,
+    AudioUpsamplingRet(UpsamplingPayload),
+    AudioUpsamplingSet(UpsamplingPayload),
+    AudioUpsamplingNotify(UpsamplingPayload),
 
-    AutomaticPowerOffButtonModeGet,
-    AutomaticPowerOffButtonModeRet,
-    AutomaticPowerOffButtonModeSet,
-    AutomaticPowerOffButtonModeNotify,
+    AutomaticPowerOffButtonModeGet(AutoPowerOffButtonType),
+    AutomaticPowerOffButtonModeRet(AutoPowerOffButtonPayload),
+    AutomaticPowerOffButtonModeSet(AutoPowerOffButtonPayload),
+    AutomaticPowerOffButtonModeNotify(AutoPowerOffButtonPayload),
 
     SpeakToChatConfigGet,
-    SpeakToChatConfigRet,
-    SpeakToChatConfigSet,
-    SpeakToChatConfigNotify,
+    SpeakToChatConfigRet(SpeakToChatConfig),
+    SpeakToChatConfigSet(SpeakToChatConfig),
+    SpeakToChatConfigNotify(SpeakToChatConfig),
 
-    JsonGet,
-    JsonRet,
+    /// Request the JSON blob of the given type.
+    JsonGet(u8),
+    #[cfg(feature = "alloc")]
+    JsonRet(JsonPayload),
 
     SomethingGet,
     SomethingRet,
@@ -221,111 +378,133 @@ impl<'a> TryFrom<&'a [u8]> for PayloadCommand1 {
     type Error = crate::Error;
 
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
-        match value[0] {
+        let (&opcode, payload) = value.split_first().ok_or(crate::Error::MissingBytes)?;
+
+        match opcode {
             0x00 => Ok(Self::InitRequest),
-            0x01 => {
-                assert!(value.len() > 3);
-                Ok(Self::InitReply([value[1], value[2], value[3]]))
-            }
+            0x01 => Ok(Self::InitReply(ProtocolInfo::try_from(payload)?)),
 
-            0x04 => Err(crate::Error::NotImplemented("Self::FwVersionRequest")),
-            0x05 => Err(crate::Error::NotImplemented("Self::FwVersionReply")),
+            0x04 => Ok(Self::DeviceInfoRequest(DeviceInfoType::try_from(
+                *payload.first().ok_or(crate::Error::MissingBytes)?,
+            )?)),
+            0x05 => Ok(Self::DeviceInfoReply(DeviceInfo::try_from(payload)?)),
 
-            0x06 => Err(crate::Error::NotImplemented("Self::Init2Request")),
-            0x07 => Err(crate::Error::NotImplemented("Self::Init2Reply")),
+            0x06 => Ok(Self::Init2Request),
+            0x07 => Ok(Self::Init2Reply(SupportFunctions::try_from(payload)?)),
 
             0x10 => Ok(PayloadCommand1::BatteryLevelRequest(BatteryType::try_from(
-                value[1],
+                *payload.first().ok_or(crate::Error::MissingBytes)?,
             )?)),
             0x11 => Ok(PayloadCommand1::BatteryLevelReply(BatteryState::try_from(
-                &value[1..],
+                payload,
             )?)),
             0x13 => Ok(PayloadCommand1::BatteryLevelNotify(BatteryState::try_from(
-                &value[1..],
+                payload,
             )?)),
 
-            0x18 => Err(crate::Error::NotImplemented("Self::AudioCodecRequest")),
-            0x19 => Err(crate::Error::NotImplemented("Self::AudioCodecReply")),
-            0x1b => Err(crate::Error::NotImplemented("Self::AudioCodecNotify")),
+            0x18 => Ok(Self::AudioCodecRequest),
+            0x19 => Ok(Self::AudioCodecReply(AudioCodec::try_from(payload)?)),
+            0x1b => Ok(Self::AudioCodecNotify(AudioCodec::try_from(payload)?)),
 
-            0x22 => Err(crate::Error::NotImplemented("Self::PowerOff")),
+            0x22 => Ok(Self::PowerOff),
 
-            0x46 => Err(crate::Error::NotImplemented("Self::SoundPositionOrModeGet")),
-            0x47 => Err(crate::Error::NotImplemented("Self::SoundPositionOrModeRet")),
-            0x48 => Err(crate::Error::NotImplemented("Self::SoundPositionOrModeSet")),
-            0x49 => Err(crate::Error::NotImplemented(
-                "Self::SoundPositionOrModeNotify",
+            0x46 => Ok(Self::SoundPositionOrModeGet(SoundPositionType::try_from(
+                *payload.first().ok_or(crate::Error::MissingBytes)?,
+            )?)),
+            0x47 => Ok(Self::SoundPositionOrModeRet(
+                SoundPositionPayload::try_from(payload)?,
+            )),
+            0x48 => Ok(Self::SoundPositionOrModeSet(
+                SoundPositionPayload::try_from(payload)?,
+            )),
+            0x49 => Ok(Self::SoundPositionOrModeNotify(
+                SoundPositionPayload::try_from(payload)?,
             )),
 
-            0x56 => Err(crate::Error::NotImplemented("Self::EqualizerGet")),
-            0x57 => Err(crate::Error::NotImplemented("Self::EqualizerRet")),
-            0x58 => Err(crate::Error::NotImplemented("Self::EqualizerSet")),
-            0x59 => Err(crate::Error::NotImplemented("Self::EqualizerNotify")),
+            0x56 => Ok(Self::EqualizerGet),
+            0x57 => Ok(Self::EqualizerRet(EqualizerPayload::try_from(payload)?)),
+            0x58 => Ok(Self::EqualizerSet(EqualizerPayload::try_from(payload)?)),
+            0x59 => Ok(Self::EqualizerNotify(EqualizerPayload::try_from(payload)?)),
 
             0x66 => Ok(Self::AmbientSoundControlGet),
-            0x67 => Ok(Self::AmbientSoundControlRet(AncPayload::try_from(
-                &value[1..],
-            )?)),
-            0x68 => Ok(Self::AmbientSoundControlSet(AncPayload::try_from(
-                &value[1..],
-            )?)),
+            0x67 => Ok(Self::AmbientSoundControlRet(AncPayload::try_from(payload)?)),
+            0x68 => Ok(Self::AmbientSoundControlSet(AncPayload::try_from(payload)?)),
             0x69 => Ok(Self::AmbientSoundControlNotify(AncPayload::try_from(
-                &value[1..],
+                payload,
             )?)),
 
-            0xa6 => Err(crate::Error::NotImplemented("Self::VolumeGet")),
-            0xa7 => Err(crate::Error::NotImplemented("Self::VolumeRet")),
-            0xa8 => Err(crate::Error::NotImplemented("Self::VolumeSet")),
-            0xa9 => Err(crate::Error::NotImplemented("Self::VolumeNotify")),
+            0xa6 => Ok(Self::VolumeGet),
+            0xa7 => Ok(Self::VolumeRet(VolumePayload::try_from(payload)?)),
+            0xa8 => Ok(Self::VolumeSet(VolumePayload::try_from(payload)?)),
+            0xa9 => Ok(Self::VolumeNotify(VolumePayload::try_from(payload)?)),
 
-            0x84 => Err(crate::Error::NotImplemented(
-                "Self::NoiseCancellingOptimizerStart",
+            0x84 => Ok(Self::NoiseCancellingOptimizerStart(
+                NcOptimizerStart::try_from(payload)?,
             )),
-            0x85 => Err(crate::Error::NotImplemented(
-                "Self::NoiseCancellingOptimizerStatus",
+            0x85 => Ok(Self::NoiseCancellingOptimizerStatus(
+                NcOptimizerStatus::try_from(payload)?,
             )),
 
-            0x86 => Err(crate::Error::NotImplemented(
-                "Self::NoiseCancellingOptimizerStateGet",
+            0x86 => Ok(Self::NoiseCancellingOptimizerStateGet),
+            0x87 => Ok(Self::NoiseCancellingOptimizerStateRet(
+                NcOptimizerState::try_from(payload)?,
             )),
-            0x87 => Err(crate::Error::NotImplemented(
-                "Self::NoiseCancellingOptimizerStateRet",
-            )),
-            0x89 => Err(crate::Error::NotImplemented(
-                "Self::NoiseCancellingOptimizerStateNotify",
+            0x89 => Ok(Self::NoiseCancellingOptimizerStateNotify(
+                NcOptimizerState::try_from(payload)?,
             )),
 
-            0xd6 => Err(crate::Error::NotImplemented("Self::TouchSensorGet")),
-            0xd7 => Err(crate::Error::NotImplemented("Self::TouchSensorRet")),
-            0xd8 => Err(crate::Error::NotImplemented("Self::TouchSensorSet")),
-            0xd9 => Err(crate::Error::NotImplemented("Self::TouchSensorNotify")),
+            0xd6 => Ok(Self::TouchSensorGet(TouchSensorType::try_from(
+                *payload.first().ok_or(crate::Error::MissingBytes)?,
+            )?)),
+            0xd7 => Ok(Self::TouchSensorRet(TouchSensorPayload::try_from(payload)?)),
+            0xd8 => Ok(Self::TouchSensorSet(TouchSensorPayload::try_from(payload)?)),
+            0xd9 => Ok(Self::TouchSensorNotify(TouchSensorPayload::try_from(
+                payload,
+            )?)),
 
-            0xe6 => Err(crate::Error::NotImplemented("Self::AudioUpsamplingGet")),
-            0xe7 => Err(crate::Error::NotImplemented("Self::AudioUpsamplingRet")),
-            0xe8 => Err(crate::Error::NotImplemented("Self::AudioUpsamplingSet")),
-            0xe9 => Err(crate::Error::NotImplemented("Self::AudioUpsamplingNotify")),
+            0xe6 => Ok(Self::AudioUpsamplingGet),
+            0xe7 => Ok(Self::AudioUpsamplingRet(UpsamplingPayload::try_from(
+                payload,
+            )?)),
+            0xe8 => Ok(Self::AudioUpsamplingSet(UpsamplingPayload::try_from(
+                payload,
+            )?)),
+            0xe9 => Ok(Self::AudioUpsamplingNotify(UpsamplingPayload::try_from(
+                payload,
+            )?)),
 
-            0xf6 => Err(crate::Error::NotImplemented(
-                "Self::AutomaticPowerOffButtonModeGet",
+            0xf6 => Ok(Self::AutomaticPowerOffButtonModeGet(
+                AutoPowerOffButtonType::try_from(
+                    *payload.first().ok_or(crate::Error::MissingBytes)?,
+                )?,
             )),
-            0xf7 => Err(crate::Error::NotImplemented(
-                "Self::AutomaticPowerOffButtonModeRet",
+            0xf7 => Ok(Self::AutomaticPowerOffButtonModeRet(
+                AutoPowerOffButtonPayload::try_from(payload)?,
             )),
-            0xf8 => Err(crate::Error::NotImplemented(
-                "Self::AutomaticPowerOffButtonModeSset",
+            0xf8 => Ok(Self::AutomaticPowerOffButtonModeSet(
+                AutoPowerOffButtonPayload::try_from(payload)?,
             )),
-            0xf9 => Err(crate::Error::NotImplemented(
-                "Self::AutomaticPowerOffButtonModeNotify",
+            0xf9 => Ok(Self::AutomaticPowerOffButtonModeNotify(
+                AutoPowerOffButtonPayload::try_from(payload)?,
             )),
 
-            0xfa => Err(crate::Error::NotImplemented("Self::SpeakToChatConfigGet")),
-            0xfb => Err(crate::Error::NotImplemented("Self::SpeakToChatConfigRet")),
-            0xfc => Err(crate::Error::NotImplemented("Self::SpeakToChatConfigSet")),
-            0xfd => Err(crate::Error::NotImplemented(
-                "Self::SpeakToChatConfigNotify",
-            )),
+            0xfa => Ok(Self::SpeakToChatConfigGet),
+            0xfb => Ok(Self::SpeakToChatConfigRet(SpeakToChatConfig::try_from(
+                payload,
+            )?)),
+            0xfc => Ok(Self::SpeakToChatConfigSet(SpeakToChatConfig::try_from(
+                payload,
+            )?)),
+            0xfd => Ok(Self::SpeakToChatConfigNotify(SpeakToChatConfig::try_from(
+                payload,
+            )?)),
 
-            0xc4 => Err(crate::Error::NotImplemented("Self::JsonGet")),
+            0xc4 => Ok(Self::JsonGet(
+                *payload.first().ok_or(crate::Error::MissingBytes)?,
+            )),
+            #[cfg(feature = "alloc")]
+            0xc9 => Ok(Self::JsonRet(JsonPayload::try_from(payload)?)),
+            #[cfg(not(feature = "alloc"))]
             0xc9 => Err(crate::Error::NotImplemented("Self::JsonRet")),
 
             0x90 => Err(crate::Error::NotImplemented("Self::SomethingGet")),
@@ -335,91 +514,67 @@ impl<'a> TryFrom<&'a [u8]> for PayloadCommand1 {
     }
 }
 
-impl<'a> Payload for PayloadCommand1 {
+impl Payload for PayloadCommand1 {
     fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
         match self {
-            Self::InitRequest => {
-                buf[0] = 0x00;
-                buf[1] = 0x00;
-                Ok(2)
-            }
-            Self::InitReply(b) => {
-                buf[0] = 0x01;
-                buf[1..4].copy_from_slice(b);
-                Ok(4)
-            }
-            Self::FwVersionRequest => Err(crate::Error::NotImplemented("0x04")),
-            Self::FwVersionReply => Err(crate::Error::NotImplemented("0x05")),
-            Self::Init2Request => Err(crate::Error::NotImplemented("0x06")),
-            Self::Init2Reply => Err(crate::Error::NotImplemented("0x07")),
-            Self::BatteryLevelRequest(b) => {
-                buf[0] = 0x10;
-                buf[1] = *b as u8;
-                Ok(2)
-            }
+            Self::InitRequest => [0x00, 0x00].as_slice().write_into(buf),
+            Self::InitReply(i) => write_command(buf, 0x01, i),
+            Self::DeviceInfoRequest(t) => write_command(buf, 0x04, t),
+            Self::DeviceInfoReply(i) => write_command(buf, 0x05, i),
+            Self::Init2Request => [0x06, 0x00].as_slice().write_into(buf),
+            Self::Init2Reply(f) => write_command(buf, 0x07, f),
+            Self::BatteryLevelRequest(b) => [0x10, *b as u8].as_slice().write_into(buf),
             Self::BatteryLevelReply(_state) => Err(crate::Error::NotImplemented("0x11")),
             Self::BatteryLevelNotify(_state) => Err(crate::Error::NotImplemented("0x13")),
-            Self::AudioCodecRequest => Err(crate::Error::NotImplemented("0x18")),
-            Self::AudioCodecReply => Err(crate::Error::NotImplemented("0x19")),
-            Self::AudioCodecNotify => Err(crate::Error::NotImplemented("0x1b")),
-            Self::PowerOff => Err(crate::Error::NotImplemented("0x22")),
-            Self::SoundPositionOrModeGet => Err(crate::Error::NotImplemented("0x46")),
-            Self::SoundPositionOrModeRet => Err(crate::Error::NotImplemented("0x47")),
-            Self::SoundPositionOrModeSet => Err(crate::Error::NotImplemented("0x48")),
-            Self::SoundPositionOrModeNotify => Err(crate::Error::NotImplemented("0x49")),
-            Self::EqualizerGet => Err(crate::Error::NotImplemented("0x56")),
-            Self::EqualizerRet => Err(crate::Error::NotImplemented("0x57")),
-            Self::EqualizerSet => Err(crate::Error::NotImplemented("0x58")),
-            Self::EqualizerNotify => Err(crate::Error::NotImplemented("0x59")),
-
-            Self::AmbientSoundControlGet => {
-                buf[0] = 0x66;
-                let len = GetAnc.write_into(&mut buf[1..])?;
-                Ok((len + 1) as u32)
-            }
-
-            Self::AmbientSoundControlRet(v) => {
-                buf[0] = 0x67;
-                let len = v.write_into(&mut buf[1..])?;
-                Ok((len + 1) as u32)
-            }
-            Self::AmbientSoundControlSet(v) => {
-                buf[0] = 0x68;
-                let len = v.write_into(&mut buf[1..])?;
-                Ok((len + 1) as u32)
+            Self::AudioCodecRequest => write_command(buf, 0x18, &audio_codec::GetAudioCodec),
+            Self::AudioCodecReply(v) => write_command(buf, 0x19, v),
+            Self::AudioCodecNotify(v) => write_command(buf, 0x1b, v),
+            Self::PowerOff => [0x22, 0x00, 0x01].as_slice().write_into(buf),
+            Self::SoundPositionOrModeGet(v) => write_command(buf, 0x46, v),
+            Self::SoundPositionOrModeRet(v) => write_command(buf, 0x47, v),
+            Self::SoundPositionOrModeSet(v) => write_command(buf, 0x48, v),
+            Self::SoundPositionOrModeNotify(v) => write_command(buf, 0x49, v),
+            Self::EqualizerGet => write_command(buf, 0x56, &equalizer::GetEqualizer),
+            Self::EqualizerRet(v) => write_command(buf, 0x57, v),
+            Self::EqualizerSet(v) => write_command(buf, 0x58, v),
+            Self::EqualizerNotify(v) => write_command(buf, 0x59, v),
+
+            Self::AmbientSoundControlGet => write_command(buf, 0x66, &GetAnc),
+            Self::AmbientSoundControlRet(v) => write_command(buf, 0x67, v),
+            Self::AmbientSoundControlSet(v) => write_command(buf, 0x68, v),
+            Self::AmbientSoundControlNotify(v) => write_command(buf, 0x69, v),
+            Self::VolumeGet => write_command(buf, 0xa6, &volume::GetVolume),
+            Self::VolumeRet(v) => write_command(buf, 0xa7, v),
+            Self::VolumeSet(v) => write_command(buf, 0xa8, v),
+            Self::VolumeNotify(v) => write_command(buf, 0xa9, v),
+            Self::NoiseCancellingOptimizerStart(v) => write_command(buf, 0x84, v),
+            Self::NoiseCancellingOptimizerStatus(v) => write_command(buf, 0x85, v),
+            Self::NoiseCancellingOptimizerStateGet => {
+                write_command(buf, 0x86, &nc_optimizer::GetNcOptimizerState)
             }
-            Self::AmbientSoundControlNotify(v) => {
-                buf[0] = 0x69;
-                let len = v.write_into(&mut buf[1..])?;
-                Ok((len + 1) as u32)
+            Self::NoiseCancellingOptimizerStateRet(v) => write_command(buf, 0x87, v),
+            Self::NoiseCancellingOptimizerStateNotify(v) => write_command(buf, 0x89, v),
+            Self::TouchSensorGet(t) => write_command(buf, 0xd6, t),
+            Self::TouchSensorRet(v) => write_command(buf, 0xd7, v),
+            Self::TouchSensorSet(v) => write_command(buf, 0xd8, v),
+            Self::TouchSensorNotify(v) => write_command(buf, 0xd9, v),
+            Self::AudioUpsamplingGet => write_command(buf, 0xe6, &upsampling::GetUpsampling),
+            Self::AudioUpsamplingRet(v) => write_command(buf, 0xe7, v),
+            Self::AudioUpsamplingSet(v) => write_command(buf, 0xe8, v),
+            Self::AudioUpsamplingNotify(v) => write_command(buf, 0xe9, v),
+            Self::AutomaticPowerOffButtonModeGet(t) => write_command(buf, 0xf6, t),
+            Self::AutomaticPowerOffButtonModeRet(v) => write_command(buf, 0xf7, v),
+            Self::AutomaticPowerOffButtonModeSet(v) => write_command(buf, 0xf8, v),
+            Self::AutomaticPowerOffButtonModeNotify(v) => write_command(buf, 0xf9, v),
+            Self::SpeakToChatConfigGet => {
+                write_command(buf, 0xfa, &speak_to_chat::GetSpeakToChatConfig)
             }
-            Self::VolumeGet => Err(crate::Error::NotImplemented("0xa6")),
-            Self::VolumeRet => Err(crate::Error::NotImplemented("0xa7")),
-            Self::VolumeSet => Err(crate::Error::NotImplemented("0xa8")),
-            Self::VolumeNotify => Err(crate::Error::NotImplemented("0xa9")),
-            Self::NoiseCancellingOptimizerStart => Err(crate::Error::NotImplemented("0x84")),
-            Self::NoiseCancellingOptimizerStatus => Err(crate::Error::NotImplemented("0x85")),
-            Self::NoiseCancellingOptimizerStateGet => Err(crate::Error::NotImplemented("0x86")),
-            Self::NoiseCancellingOptimizerStateRet => Err(crate::Error::NotImplemented("0x87")),
-            Self::NoiseCancellingOptimizerStateNotify => Err(crate::Error::NotImplemented("0x89")),
-            Self::TouchSensorGet => Err(crate::Error::NotImplemented("0xd6")),
-            Self::TouchSensorRet => Err(crate::Error::NotImplemented("0xd7")),
-            Self::TouchSensorSet => Err(crate::Error::NotImplemented("0xd8")),
-            Self::TouchSensorNotify => Err(crate::Error::NotImplemented("0xd9")),
-            Self::AudioUpsamplingGet => Err(crate::Error::NotImplemented("0xe6")),
-            Self::AudioUpsamplingRet => Err(crate::Error::NotImplemented("0xe7")),
-            Self::AudioUpsamplingSet => Err(crate::Error::NotImplemented("0xe8")),
-            Self::AudioUpsamplingNotify => Err(crate::Error::NotImplemented("0xe9")),
-            Self::AutomaticPowerOffButtonModeGet => Err(crate::Error::NotImplemented("0xf6")),
-            Self::AutomaticPowerOffButtonModeRet => Err(crate::Error::NotImplemented("0xf7")),
-            Self::AutomaticPowerOffButtonModeSet => Err(crate::Error::NotImplemented("0xf8")),
-            Self::AutomaticPowerOffButtonModeNotify => Err(crate::Error::NotImplemented("0xf9")),
-            Self::SpeakToChatConfigGet => Err(crate::Error::NotImplemented("0xfa")),
-            Self::SpeakToChatConfigRet => Err(crate::Error::NotImplemented("0xfb")),
-            Self::SpeakToChatConfigSet => Err(crate::Error::NotImplemented("0xfc")),
-            Self::SpeakToChatConfigNotify => Err(crate::Error::NotImplemented("0xfd")),
-            Self::JsonGet => Err(crate::Error::NotImplemented("0xc4")),
-            Self::JsonRet => Err(crate::Error::NotImplemented("0xc9")),
+            Self::SpeakToChatConfigRet(v) => write_command(buf, 0xfb, v),
+            Self::SpeakToChatConfigSet(v) => write_command(buf, 0xfc, v),
+            Self::SpeakToChatConfigNotify(v) => write_command(buf, 0xfd, v),
+            Self::JsonGet(t) => [0xc4, *t].as_slice().write_into(buf),
+            #[cfg(feature = "alloc")]
+            Self::JsonRet(v) => write_command(buf, 0xc9, v),
             Self::SomethingGet => Err(crate::Error::NotImplemented("0x90")),
             Self::SomethingRet => Err(crate::Error::NotImplemented("0x91")),
         }
@@ -430,38 +585,73 @@ impl<'a> TryFrom<&'a [u8]> for Packet {
     type Error = crate::TryFromPacketError;
 
     fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
-        // TODO HEADER / END / CHECKSUM
-        //
-        //
-        let seqnum = value[2];
+        let frame_error = |error| crate::TryFromPacketError {
+            seqnum: None,
+            error,
+        };
 
-        let content = match value[1] {
-            0x1 => Ok(PacketContent::Ack),
-            0x0c => {
-                let packet_size = u32::from_be_bytes(
-                    value[3..][0..4]
-                        .try_into()
-                        .map_err(|e: TryFromSliceError| Into::<crate::Error>::into(e))
-                        .map_err(|error| crate::TryFromPacketError { seqnum, error })?,
-                ); // TODO
+        if value.len() < 9 {
+            return Err(frame_error(crate::Error::MissingBytes));
+        }
 
-                let payload_raw = &value[7..7 + packet_size as usize];
+        if value[0] != MESSAGE_HEADER {
+            return Err(frame_error(crate::Error::InvalidHeader(value[0])));
+        }
 
-                let payload = PayloadCommand1::try_from(payload_raw);
+        // Compared before adding the header size, which could overflow on 32-bit targets.
+        let packet_size = u32::from_be_bytes([value[3], value[4], value[5], value[6]]) as usize;
+        if packet_size > value.len() - 9 {
+            return Err(frame_error(crate::Error::MissingBytes));
+        }
+        let end = 7 + packet_size;
 
-                match payload {
-                    Ok(p) => Ok(PacketContent::Command1(p)),
-                    Err(error) => Err(crate::TryFromPacketError { seqnum, error }),
-                }
+        if value[end + 1] != MESSAGE_TRAILER {
+            return Err(frame_error(crate::Error::InvalidTrailer(value[end + 1])));
+        }
+
+        let checksum = checksum(&value[1..end]);
+        if value[end] != checksum {
+            return Err(frame_error(crate::Error::InvalidChecksum {
+                expected: checksum,
+                actual: value[end],
+            }));
+        }
+
+        let seqnum = value[2];
+        let payload_raw = &value[7..end];
+
+        let data_type = DataType::from(value[1]);
+        let content = match data_type {
+            DataType::Ack => Ok(PacketContent::Ack),
+            DataType::DataMdr => {
+                PayloadCommand1::try_from(payload_raw).map(PacketContent::Command1)
+            }
+            DataType::DataMdrNo2 => {
+                PayloadCommand2::try_from(payload_raw).map(PacketContent::Command2)
+            }
+            DataType::ShotMdr => {
+                PayloadCommand1::try_from(payload_raw).map(PacketContent::ShotCommand1)
             }
-            0x0e => Ok(PacketContent::Command2),
-            _ => todo!(),
-        }?;
+            DataType::ShotMdrNo2 => {
+                PayloadCommand2::try_from(payload_raw).map(PacketContent::ShotCommand2)
+            }
+            data_type => RawPayload::try_from(payload_raw)
+                .map(|payload| PacketContent::Raw { data_type, payload }),
+        }
+        .map_err(|error| crate::TryFromPacketError {
+            // Shot packets are not acknowledged, even when their payload is invalid.
+            seqnum: (!data_type.is_shot()).then_some(seqnum),
+            error,
+        })?;
 
         Ok(Packet { seqnum, content })
     }
 }
 
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |acc: u8, x: &u8| acc.wrapping_add(*x))
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct AncPayload {
     pub anc_mode: AncMode,
@@ -483,50 +673,116 @@ pub trait Payload {
 
 impl Payload for AncPayload {
     fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
-        // TODO invalid buffer size
-        buf[0] = 0x02;
-        buf[1] = if self.anc_mode == AncMode::Off {
-            0x00
-        } else {
-            0x11
-        };
-        buf[2] = 0x02;
-        buf[3] = match self.anc_mode {
-            AncMode::Off | AncMode::AmbiantMode => 0,
-            AncMode::On => 0x02,
-            AncMode::Wind => 0x01,
-        };
-        buf[4] = 0x01;
-        buf[5] = if self.focus_on_voice { 0x01 } else { 0x00 };
-        buf[6] = match self.anc_mode {
-            AncMode::Off | AncMode::AmbiantMode => self.ambiant_level,
-            AncMode::On | AncMode::Wind => 0x1,
-        };
-        Ok(7)
+        [
+            0x02,
+            if self.anc_mode == AncMode::Off {
+                0x00
+            } else {
+                0x11
+            },
+            0x02,
+            match self.anc_mode {
+                AncMode::Off | AncMode::AmbiantMode => 0,
+                AncMode::On => 0x02,
+                AncMode::Wind => 0x01,
+            },
+            0x01,
+            if self.focus_on_voice { 0x01 } else { 0x00 },
+            match self.anc_mode {
+                AncMode::Off | AncMode::AmbiantMode => self.ambiant_level,
+                AncMode::On | AncMode::Wind => 0x1,
+            },
+        ]
+        .as_slice()
+        .write_into(buf)
     }
 }
 
 impl Payload for &[u8] {
     fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        ensure_capacity(buf, self.len())?;
         buf[0..self.len()].copy_from_slice(self);
         Ok(self.len() as u32)
     }
 }
 
+/// Check that `buf` can hold `needed` bytes.
+fn ensure_capacity(buf: &[u8], needed: usize) -> crate::Result<()> {
+    if buf.len() < needed {
+        return Err(crate::Error::BufferTooSmall {
+            needed,
+            available: buf.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Write `opcode` followed by `payload`, returning the total size.
+fn write_command(buf: &mut [u8], opcode: u8, payload: &dyn Payload) -> crate::Result<u32> {
+    ensure_capacity(buf, 1)?;
+    buf[0] = opcode;
+    Ok(payload.write_into(&mut buf[1..])? + 1)
+}
+
 impl Payload for () {
     fn write_into(&self, _: &mut [u8]) -> crate::Result<u32> {
         Ok(0)
     }
 }
 
+/// Payload kept as raw bytes, for commands that are not decoded.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RawPayload {
+    len: usize,
+    data: [u8; RawPayload::CAPACITY],
+}
+
+impl RawPayload {
+    pub const CAPACITY: usize = 128;
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+}
+
+impl Debug for RawPayload {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:02x?}", self.as_slice())
+    }
+}
+
+impl TryFrom<&[u8]> for RawPayload {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        if value.len() > Self::CAPACITY {
+            return Err(crate::Error::BufferTooSmall {
+                needed: value.len(),
+                available: Self::CAPACITY,
+            });
+        }
+
+        let mut data = [0u8; Self::CAPACITY];
+        data[..value.len()].copy_from_slice(value);
+        Ok(Self {
+            len: value.len(),
+            data,
+        })
+    }
+}
+
+impl Payload for RawPayload {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        self.as_slice().write_into(buf)
+    }
+}
+
 #[derive(Debug)]
 pub struct GetAnc;
 
 impl Payload for GetAnc {
     fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
-        buf[0] = 0x02;
-
-        Ok(1)
+        [0x02].as_slice().write_into(buf)
     }
 }
 
@@ -534,35 +790,34 @@ impl TryFrom<&[u8]> for AncPayload {
     type Error = crate::Error;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        assert_eq!(7, value.len());
+        if value.len() < 7 {
+            return Err(crate::Error::MissingBytes);
+        }
+
+        let invalid_mode = |value| crate::Error::InvalidValueForEnum {
+            what: "anc mode",
+            value,
+        };
         let mode = match value[1] {
             0x00 => AncMode::Off,
-            0x01 => {
-                if value[2] == 0x00 {
-                    // Only ANC  and Ambient Sound supported?
-                    if value[3] == 0x00 {
-                        AncMode::AmbiantMode
-                    } else if value[3] == 0x01 {
-                        AncMode::On
-                    } else {
-                        unimplemented!()
-                    }
-                } else if value[2] == 0x02 {
-                    // Supports wind noise reduction
-                    if value[3] == 0x00 {
-                        AncMode::AmbiantMode
-                    } else if value[3] == 0x01 {
-                        AncMode::Wind
-                    } else if value[3] == 0x02 {
-                        AncMode::On
-                    } else {
-                        unimplemented!()
-                    }
-                } else {
-                    unimplemented!()
+            // 0x11 is what we send when setting the mode
+            0x01 | 0x11 => match (value[2], value[3]) {
+                // Only ANC  and Ambient Sound supported?
+                (0x00, 0x00) => AncMode::AmbiantMode,
+                (0x00, 0x01) => AncMode::On,
+                // Supports wind noise reduction
+                (0x02, 0x00) => AncMode::AmbiantMode,
+                (0x02, 0x01) => AncMode::Wind,
+                (0x02, 0x02) => AncMode::On,
+                (0x00 | 0x02, mode) => return Err(invalid_mode(mode)),
+                (anc_type, _) => {
+                    return Err(crate::Error::InvalidValueForEnum {
+                        what: "anc type",
+                        value: anc_type,
+                    })
                 }
-            }
-            _ => unimplemented!(),
+            },
+            mode => return Err(invalid_mode(mode)),
         };
 
         let focus_on_voice = value[5] == 0x01;
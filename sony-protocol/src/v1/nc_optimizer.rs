@@ -0,0 +1,134 @@
+use super::Payload;
+
+/// Inquired type of the noise cancelling optimizer commands.
+const NC_OPTIMIZER_TYPE: u8 = 0x01;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct NcOptimizerStart {
+    /// `false` cancels a running optimization.
+    pub start: bool,
+}
+
+impl TryFrom<&[u8]> for NcOptimizerStart {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let [_, _, start] = value
+            .get(..3)
+            .ok_or(crate::Error::MissingBytes)?
+            .try_into()?;
+
+        Ok(Self {
+            start: start == 0x01,
+        })
+    }
+}
+
+impl Payload for NcOptimizerStart {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [NC_OPTIMIZER_TYPE, 0x00, self.start as u8]
+            .as_slice()
+            .write_into(buf)
+    }
+}
+
+/// Progress of the optimization, notified while it runs.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NcOptimizerStatus {
+    NotRunning,
+    Started,
+    Analyzing,
+    Finished,
+    Other(u8),
+}
+
+impl From<u8> for NcOptimizerStatus {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => Self::NotRunning,
+            0x01 => Self::Started,
+            0x10 => Self::Analyzing,
+            0x11 => Self::Finished,
+            value => Self::Other(value),
+        }
+    }
+}
+
+impl From<NcOptimizerStatus> for u8 {
+    fn from(value: NcOptimizerStatus) -> Self {
+        match value {
+            NcOptimizerStatus::NotRunning => 0x00,
+            NcOptimizerStatus::Started => 0x01,
+            NcOptimizerStatus::Analyzing => 0x10,
+            NcOptimizerStatus::Finished => 0x11,
+            NcOptimizerStatus::Other(value) => value,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for NcOptimizerStatus {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let [_, _, status] = value
+            .get(..3)
+            .ok_or(crate::Error::MissingBytes)?
+            .try_into()?;
+
+        Ok(status.into())
+    }
+}
+
+impl Payload for NcOptimizerStatus {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [NC_OPTIMIZER_TYPE, 0x00, (*self).into()]
+            .as_slice()
+            .write_into(buf)
+    }
+}
+
+/// Result of the last optimization.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct NcOptimizerState {
+    /// Optimized for the wearer's head and the way the headset is worn.
+    pub personal_optimized: bool,
+    /// Atmospheric pressure the headset was optimized for, in tenths of atmosphere.
+    pub atmospheric_pressure: Option<u8>,
+}
+
+impl TryFrom<&[u8]> for NcOptimizerState {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let [_, personal_optimized, pressure] = value
+            .get(..3)
+            .ok_or(crate::Error::MissingBytes)?
+            .try_into()?;
+
+        Ok(Self {
+            personal_optimized: personal_optimized == 0x01,
+            atmospheric_pressure: (pressure != 0).then_some(pressure),
+        })
+    }
+}
+
+impl Payload for NcOptimizerState {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [
+            NC_OPTIMIZER_TYPE,
+            self.personal_optimized as u8,
+            self.atmospheric_pressure.unwrap_or(0),
+        ]
+        .as_slice()
+        .write_into(buf)
+    }
+}
+
+#[derive(Debug)]
+pub struct GetNcOptimizerState;
+
+impl Payload for GetNcOptimizerState {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [NC_OPTIMIZER_TYPE].as_slice().write_into(buf)
+    }
+}
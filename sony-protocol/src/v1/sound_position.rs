@@ -0,0 +1,134 @@
+use super::Payload;
+
+/// Setting read or written by the sound position and surround (VPT) commands.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum SoundPositionType {
+    Surround = 0x01,
+    Position = 0x02,
+}
+
+impl TryFrom<u8> for SoundPositionType {
+    type Error = crate::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(Self::Surround),
+            0x02 => Ok(Self::Position),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "sound position type",
+                value,
+            }),
+        }
+    }
+}
+
+impl Payload for SoundPositionType {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [*self as u8].as_slice().write_into(buf)
+    }
+}
+
+/// Position the sound appears to come from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum SoundPosition {
+    Off = 0x00,
+    FrontLeft = 0x01,
+    FrontRight = 0x02,
+    Front = 0x03,
+    RearLeft = 0x11,
+    RearRight = 0x12,
+}
+
+impl TryFrom<u8> for SoundPosition {
+    type Error = crate::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Self::Off),
+            0x01 => Ok(Self::FrontLeft),
+            0x02 => Ok(Self::FrontRight),
+            0x03 => Ok(Self::Front),
+            0x11 => Ok(Self::RearLeft),
+            0x12 => Ok(Self::RearRight),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "sound position",
+                value,
+            }),
+        }
+    }
+}
+
+/// Virtual room simulated by the headset.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum SurroundMode {
+    Off = 0x00,
+    OutdoorStage = 0x01,
+    Arena = 0x02,
+    ConcertHall = 0x03,
+    Club = 0x04,
+}
+
+impl TryFrom<u8> for SurroundMode {
+    type Error = crate::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Self::Off),
+            0x01 => Ok(Self::OutdoorStage),
+            0x02 => Ok(Self::Arena),
+            0x03 => Ok(Self::ConcertHall),
+            0x04 => Ok(Self::Club),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "surround mode",
+                value,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SoundPositionPayload {
+    Surround(SurroundMode),
+    Position(SoundPosition),
+}
+
+impl SoundPositionPayload {
+    pub fn setting_type(&self) -> SoundPositionType {
+        match self {
+            Self::Surround(_) => SoundPositionType::Surround,
+            Self::Position(_) => SoundPositionType::Position,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for SoundPositionPayload {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let [setting_type, value] = value
+            .get(..2)
+            .ok_or(crate::Error::MissingBytes)?
+            .try_into()?;
+
+        match SoundPositionType::try_from(setting_type)? {
+            SoundPositionType::Surround => Ok(Self::Surround(SurroundMode::try_from(value)?)),
+            SoundPositionType::Position => Ok(Self::Position(SoundPosition::try_from(value)?)),
+        }
+    }
+}
+
+impl Payload for SoundPositionPayload {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        let value = match self {
+            Self::Surround(mode) => *mode as u8,
+            Self::Position(position) => *position as u8,
+        };
+
+        [self.setting_type() as u8, value]
+            .as_slice()
+            .write_into(buf)
+    }
+}
@@ -0,0 +1,108 @@
+use super::Payload;
+
+/// Setting type of the speak-to-chat switch in the automatic power off and button mode
+/// commands.
+pub(super) const SPEAK_TO_CHAT_TYPE: u8 = 0x0c;
+
+/// Inquired type of the speak-to-chat configuration commands.
+const SPEAK_TO_CHAT_CONFIG_TYPE: u8 = 0x05;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum SpeakToChatSensitivity {
+    Auto = 0x00,
+    High = 0x01,
+    Low = 0x02,
+}
+
+impl TryFrom<u8> for SpeakToChatSensitivity {
+    type Error = crate::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Self::Auto),
+            0x01 => Ok(Self::High),
+            0x02 => Ok(Self::Low),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "speak-to-chat sensitivity",
+                value,
+            }),
+        }
+    }
+}
+
+/// Time after which the headset goes back to the previous mode once the voice stopped.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum SpeakToChatTimeout {
+    Short = 0x00,
+    Standard = 0x01,
+    Long = 0x02,
+    Off = 0x03,
+}
+
+impl TryFrom<u8> for SpeakToChatTimeout {
+    type Error = crate::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Self::Short),
+            0x01 => Ok(Self::Standard),
+            0x02 => Ok(Self::Long),
+            0x03 => Ok(Self::Off),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "speak-to-chat timeout",
+                value,
+            }),
+        }
+    }
+}
+
+/// Speak-to-chat settings, enabling it is done with
+/// [`super::AutoPowerOffButtonPayload::SpeakToChat`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SpeakToChatConfig {
+    pub sensitivity: SpeakToChatSensitivity,
+    pub voice_focus: bool,
+    pub timeout: SpeakToChatTimeout,
+}
+
+impl TryFrom<&[u8]> for SpeakToChatConfig {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let [_, _, sensitivity, voice_focus, timeout] = value
+            .get(..5)
+            .ok_or(crate::Error::MissingBytes)?
+            .try_into()?;
+
+        Ok(Self {
+            sensitivity: SpeakToChatSensitivity::try_from(sensitivity)?,
+            voice_focus: voice_focus == 0x01,
+            timeout: SpeakToChatTimeout::try_from(timeout)?,
+        })
+    }
+}
+
+impl Payload for SpeakToChatConfig {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [
+            SPEAK_TO_CHAT_CONFIG_TYPE,
+            0x00,
+            self.sensitivity as u8,
+            self.voice_focus as u8,
+            self.timeout as u8,
+        ]
+        .as_slice()
+        .write_into(buf)
+    }
+}
+
+#[derive(Debug)]
+pub struct GetSpeakToChatConfig;
+
+impl Payload for GetSpeakToChatConfig {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [SPEAK_TO_CHAT_CONFIG_TYPE].as_slice().write_into(buf)
+    }
+}
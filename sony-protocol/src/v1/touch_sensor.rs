@@ -0,0 +1,68 @@
+use super::Payload;
+
+/// Setting read or written by the touch sensor commands.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum TouchSensorType {
+    TouchPanel = 0xd2,
+}
+
+impl TryFrom<u8> for TouchSensorType {
+    type Error = crate::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0xd2 => Ok(Self::TouchPanel),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "touch sensor type",
+                value,
+            }),
+        }
+    }
+}
+
+impl Payload for TouchSensorType {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [*self as u8].as_slice().write_into(buf)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TouchSensorPayload {
+    TouchPanel { enabled: bool },
+}
+
+impl TouchSensorPayload {
+    pub fn sensor_type(&self) -> TouchSensorType {
+        match self {
+            Self::TouchPanel { .. } => TouchSensorType::TouchPanel,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for TouchSensorPayload {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (&sensor_type, payload) = value.split_first().ok_or(crate::Error::MissingBytes)?;
+
+        match TouchSensorType::try_from(sensor_type)? {
+            TouchSensorType::TouchPanel => match payload {
+                [_, enabled, ..] => Ok(Self::TouchPanel {
+                    enabled: *enabled == 0x01,
+                }),
+                _ => Err(crate::Error::MissingBytes),
+            },
+        }
+    }
+}
+
+impl Payload for TouchSensorPayload {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        match self {
+            Self::TouchPanel { enabled } => [self.sensor_type() as u8, 0x01, *enabled as u8]
+                .as_slice()
+                .write_into(buf),
+        }
+    }
+}
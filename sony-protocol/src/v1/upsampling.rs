@@ -0,0 +1,67 @@
+use super::Payload;
+
+/// Inquired type of the audio upsampling commands.
+const UPSAMPLING_TYPE: u8 = 0x02;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum UpsamplingMode {
+    Off = 0x00,
+    /// DSEE, or DSEE Extreme on the models that have it.
+    On = 0x01,
+    /// Only upsample compressed sources.
+    Auto = 0x02,
+}
+
+impl TryFrom<u8> for UpsamplingMode {
+    type Error = crate::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Self::Off),
+            0x01 => Ok(Self::On),
+            0x02 => Ok(Self::Auto),
+            value => Err(crate::Error::InvalidValueForEnum {
+                what: "upsampling mode",
+                value,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct UpsamplingPayload {
+    pub mode: UpsamplingMode,
+}
+
+impl TryFrom<&[u8]> for UpsamplingPayload {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let [_, _, mode] = value
+            .get(..3)
+            .ok_or(crate::Error::MissingBytes)?
+            .try_into()?;
+
+        Ok(Self {
+            mode: UpsamplingMode::try_from(mode)?,
+        })
+    }
+}
+
+impl Payload for UpsamplingPayload {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [UPSAMPLING_TYPE, 0x00, self.mode as u8]
+            .as_slice()
+            .write_into(buf)
+    }
+}
+
+#[derive(Debug)]
+pub struct GetUpsampling;
+
+impl Payload for GetUpsampling {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [UPSAMPLING_TYPE].as_slice().write_into(buf)
+    }
+}
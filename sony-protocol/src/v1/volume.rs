@@ -0,0 +1,62 @@
+use super::Payload;
+
+/// Inquired type of the volume commands.
+const VOLUME_TYPE: u8 = 0x20;
+
+/// Volume of the headset itself, independent from the source's volume.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct VolumePayload {
+    pub level: u8,
+}
+
+impl VolumePayload {
+    pub const MAX: u8 = 30;
+
+    fn check(&self) -> crate::Result<()> {
+        if self.level > Self::MAX {
+            return Err(crate::Error::OutOfRange {
+                what: "volume",
+                value: self.level.into(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&[u8]> for VolumePayload {
+    type Error = crate::Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let [inquired_type, level] = value
+            .get(..2)
+            .ok_or(crate::Error::MissingBytes)?
+            .try_into()?;
+
+        if inquired_type != VOLUME_TYPE {
+            return Err(crate::Error::InvalidValueForEnum {
+                what: "volume type",
+                value: inquired_type,
+            });
+        }
+
+        let volume = Self { level };
+        volume.check()?;
+        Ok(volume)
+    }
+}
+
+impl Payload for VolumePayload {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        self.check()?;
+        [VOLUME_TYPE, self.level].as_slice().write_into(buf)
+    }
+}
+
+#[derive(Debug)]
+pub struct GetVolume;
+
+impl Payload for GetVolume {
+    fn write_into(&self, buf: &mut [u8]) -> crate::Result<u32> {
+        [VOLUME_TYPE].as_slice().write_into(buf)
+    }
+}
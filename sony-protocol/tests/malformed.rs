@@ -0,0 +1,168 @@
+use sony_protocol::{
+    v1::{AncPayload, BatteryState, Packet, PayloadCommand1, PayloadCommand2},
+    Device, Error, State, Timestamp, TryFromPacketError,
+};
+
+const NOW: Timestamp = Timestamp::from_millis(0);
+
+/// Payloads of every opcode, truncated at every length and filled with various patterns.
+fn corpus() -> Vec<Vec<u8>> {
+    let fills: [fn(usize) -> u8; 4] = [|_| 0x00, |_| 0xff, |i| i as u8, |i| 0x3c + (i % 3) as u8];
+
+    let mut corpus = vec![Vec::new()];
+    for opcode in 0..=u8::MAX {
+        for fill in fills {
+            for len in 0..16 {
+                let mut payload = vec![opcode];
+                payload.extend((0..len).map(fill));
+                corpus.push(payload);
+            }
+        }
+    }
+    corpus
+}
+
+fn frame(data_type: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x3e, data_type, 0x00];
+    frame.extend((payload.len() as u32).to_be_bytes());
+    frame.extend(payload);
+    frame.push(frame[1..].iter().fold(0u8, |acc, b| acc.wrapping_add(*b)));
+    frame.push(0x3c);
+    frame
+}
+
+#[test]
+fn payload_decoders_do_not_panic() {
+    for payload in corpus() {
+        let _ = PayloadCommand1::try_from(payload.as_slice());
+        let _ = PayloadCommand2::try_from(payload.as_slice());
+        let _ = AncPayload::try_from(payload.as_slice());
+        let _ = BatteryState::try_from(payload.as_slice());
+    }
+}
+
+#[test]
+fn truncated_payloads_are_missing_bytes() {
+    let payloads: [&[u8]; 5] = [
+        &[0x05, 0x01],
+        &[0x10],
+        &[0x11, 0x01, 0x50, 0x00, 0x50],
+        &[0x67, 0x02, 0x01, 0x02, 0x01, 0x00],
+        &[0x68, 0x02, 0x11],
+    ];
+
+    for payload in payloads {
+        assert_eq!(
+            Err(Error::MissingBytes),
+            PayloadCommand1::try_from(payload),
+            "{:02x?}",
+            payload
+        );
+    }
+}
+
+#[test]
+fn invalid_anc_mode_is_rejected() {
+    let payload = [0x02, 0x01, 0x02, 0x07, 0x01, 0x00, 0x00];
+    assert_eq!(
+        Err(sony_protocol::Error::InvalidValueForEnum {
+            what: "anc mode",
+            value: 0x07
+        }),
+        AncPayload::try_from(payload.as_slice())
+    );
+}
+
+#[test]
+fn packet_decoder_does_not_panic() {
+    for data_type in 0..=u8::MAX {
+        for payload in corpus().iter().step_by(7) {
+            let frame = frame(data_type, payload);
+            for end in 0..=frame.len() {
+                let _ = Packet::try_from(&frame[..end]);
+            }
+        }
+    }
+
+    for payload in corpus().iter().step_by(31) {
+        for size in 0xffff_fff7..=0xffff_ffffu32 {
+            let mut frame = frame(0x0c, payload);
+            frame[3..7].copy_from_slice(&size.to_be_bytes());
+            for end in 0..=frame.len() {
+                let _ = Packet::try_from(&frame[..end]);
+            }
+        }
+    }
+}
+
+#[test]
+fn forged_length_is_missing_bytes() {
+    for size in 0xffff_fff7..=0xffff_ffffu32 {
+        for len in 9..16 {
+            let mut frame = vec![0x3e, 0x0c, 0x00];
+            frame.extend(size.to_be_bytes());
+            frame.resize(len - 1, 0x00);
+            frame.push(0x3c);
+
+            assert_eq!(
+                Err(Error::MissingBytes),
+                Packet::try_from(frame.as_slice()).map_err(|e| e.error),
+                "{:02x?}",
+                frame
+            );
+        }
+    }
+}
+
+#[test]
+fn corrupted_frames_report_the_invalid_byte() {
+    let valid = frame(0x0c, &[0x22, 0x00, 0x01]);
+    let end = valid.len() - 2;
+    assert_eq!(0x32, valid[end]);
+
+    let mut header = valid.clone();
+    header[0] = 0x00;
+    let mut checksum = valid.clone();
+    checksum[end] = 0x33;
+    let mut trailer = valid.clone();
+    trailer[end + 1] = 0x00;
+
+    for (frame, error) in [
+        (header, Error::InvalidHeader(0x00)),
+        (
+            checksum,
+            Error::InvalidChecksum {
+                expected: 0x32,
+                actual: 0x33,
+            },
+        ),
+        (trailer, Error::InvalidTrailer(0x00)),
+    ] {
+        assert_eq!(
+            Err(TryFromPacketError {
+                seqnum: None,
+                error
+            }),
+            Packet::try_from(frame.as_slice()),
+            "{:02x?}",
+            frame
+        );
+    }
+}
+
+#[test]
+fn device_survives_malformed_frames() {
+    let mut device = Device::default();
+    for payload in corpus() {
+        for data_type in [0x0c, 0x0e, 0x1c, 0x42] {
+            device.received_packet(&frame(data_type, &payload)).unwrap();
+            loop {
+                match device.poll(NOW).unwrap() {
+                    State::WaitingPacket(_) => break,
+                    State::ReceivedPacket(_) | State::SendPacket(..) => (),
+                    state => panic!("unexpected state {:?}", state),
+                }
+            }
+        }
+    }
+}
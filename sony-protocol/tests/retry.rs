@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use sony_protocol::{
+    v1::{Packet, PacketContent, PayloadCommand1},
+    Device, RetryPolicy, State, Timestamp,
+};
+
+fn at(millis: u64) -> Timestamp {
+    Timestamp::from_millis(millis)
+}
+
+fn init_request() -> PacketContent {
+    PacketContent::Command1(PayloadCommand1::InitRequest)
+}
+
+fn ack(seqnum: u8) -> Vec<u8> {
+    let mut buf = [0u8; 16];
+    let size = Packet::new(seqnum, PacketContent::Ack)
+        .write_into(&mut buf)
+        .unwrap();
+    buf[..size].to_vec()
+}
+
+fn assert_sent(device: &mut Device, now: Timestamp) {
+    let state = device.poll(now).unwrap();
+    assert!(matches!(state, State::SendPacket(..)), "{:?}", state);
+}
+
+fn assert_waiting(device: &mut Device, now: Timestamp, deadline: Option<Timestamp>) {
+    match device.poll(now).unwrap() {
+        State::WaitingPacket(d) => assert_eq!(deadline, d),
+        state => panic!("unexpected state {:?}", state),
+    }
+}
+
+#[test]
+fn retransmit_after_timeout() {
+    let mut device = Device::new(RetryPolicy {
+        initial_timeout: Duration::from_millis(100),
+        backoff: 1,
+        max_attempts: 3,
+    });
+    device.send_packet(init_request()).unwrap();
+
+    assert_sent(&mut device, at(1000));
+    assert_waiting(&mut device, at(1000), Some(at(1100)));
+    assert_waiting(&mut device, at(1099), Some(at(1100)));
+    assert_sent(&mut device, at(1100));
+    assert_waiting(&mut device, at(1100), Some(at(1200)));
+}
+
+#[test]
+fn backoff_increases_timeout() {
+    let mut device = Device::new(RetryPolicy {
+        initial_timeout: Duration::from_millis(100),
+        backoff: 2,
+        max_attempts: 4,
+    });
+    device.send_packet(init_request()).unwrap();
+
+    assert_sent(&mut device, at(0));
+    assert_waiting(&mut device, at(0), Some(at(100)));
+    assert_sent(&mut device, at(100));
+    assert_waiting(&mut device, at(100), Some(at(300)));
+    assert_sent(&mut device, at(300));
+    assert_waiting(&mut device, at(300), Some(at(700)));
+}
+
+#[test]
+fn give_up_after_max_attempts() {
+    let mut device = Device::new(RetryPolicy {
+        initial_timeout: Duration::from_millis(100),
+        backoff: 1,
+        max_attempts: 2,
+    });
+    let id = device.send_packet(init_request()).unwrap();
+
+    assert_sent(&mut device, at(0));
+    assert_sent(&mut device, at(100));
+    assert!(matches!(device.poll(at(200)).unwrap(), State::PacketFailed(i) if i == id));
+    assert_waiting(&mut device, at(200), None);
+}
+
+#[test]
+fn next_packet_is_sent_after_failure() {
+    let mut device = Device::new(RetryPolicy {
+        initial_timeout: Duration::from_millis(100),
+        backoff: 1,
+        max_attempts: 1,
+    });
+    device.send_packet(init_request()).unwrap();
+    device.send_packet(init_request()).unwrap();
+
+    assert_sent(&mut device, at(0));
+    assert!(matches!(
+        device.poll(at(100)).unwrap(),
+        State::PacketFailed(_)
+    ));
+    assert_sent(&mut device, at(100));
+}
+
+#[test]
+fn ack_stops_retransmission() {
+    let mut device = Device::default();
+    let id = device.send_packet(init_request()).unwrap();
+
+    assert_sent(&mut device, at(0));
+    device.received_packet(&ack(1)).unwrap();
+    assert!(matches!(device.poll(at(10)).unwrap(), State::PacketAcked(i) if i == id));
+    assert_waiting(&mut device, at(100_000), None);
+}
+
+#[test]
+fn policy_that_would_not_wait_is_clamped() {
+    let mut device = Device::new(RetryPolicy {
+        initial_timeout: Duration::ZERO,
+        backoff: 0,
+        max_attempts: 0,
+    });
+    let id = device.send_packet(init_request()).unwrap();
+
+    assert_sent(&mut device, at(0));
+    assert_waiting(&mut device, at(0), Some(at(1)));
+    assert!(matches!(device.poll(at(1)).unwrap(), State::PacketFailed(i) if i == id));
+
+    let mut device = Device::new(RetryPolicy {
+        initial_timeout: Duration::from_millis(100),
+        backoff: 0,
+        max_attempts: 3,
+    });
+    device.send_packet(init_request()).unwrap();
+
+    assert_sent(&mut device, at(0));
+    assert_sent(&mut device, at(100));
+    assert_waiting(&mut device, at(100), Some(at(200)));
+}
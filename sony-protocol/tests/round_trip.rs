@@ -0,0 +1,770 @@
+use sony_protocol::{
+    v1::{
+        AncMode, AncPayload, AudioCodec, AutoPowerOff, AutoPowerOffButtonPayload,
+        AutoPowerOffButtonType, ButtonFunction, Capabilities, DeviceInfo, DeviceInfoType, EqBands,
+        EqPreset, EqualizerPayload, Feature, InfoString, NcAmbButtonMode, NcOptimizerStart,
+        NcOptimizerState, NcOptimizerStatus, Packet, PacketContent, PayloadCommand1,
+        PayloadCommand2, ProtocolInfo, SoundPosition, SoundPositionPayload, SoundPositionType,
+        SpeakToChatConfig, SpeakToChatSensitivity, SpeakToChatTimeout, SupportFunctions,
+        SurroundMode, TouchSensorPayload, TouchSensorType, UpsamplingMode, UpsamplingPayload,
+        VoiceGuidance, VolumePayload,
+    },
+    Device, Error, State, Timestamp,
+};
+
+const NOW: Timestamp = Timestamp::from_millis(0);
+
+fn encode(packet: Packet) -> Vec<u8> {
+    let mut buf = [0u8; 1024];
+    let size = packet.write_into(&mut buf).unwrap();
+    buf[..size].to_vec()
+}
+
+fn decode(bytes: &[u8]) -> Packet {
+    let mut device = Device::default();
+    assert_eq!(bytes.len(), device.received_packet(bytes).unwrap());
+
+    // The device acknowledges the packet before handing it out.
+    assert!(matches!(device.poll(NOW).unwrap(), State::SendPacket(..)));
+    match device.poll(NOW).unwrap() {
+        State::ReceivedPacket(packet) => packet,
+        state => panic!("unexpected state {:?}", state),
+    }
+}
+
+fn encode_payload(payload: &PayloadCommand1) -> Vec<u8> {
+    let mut buf = [0u8; 64];
+    let size = sony_protocol::v1::Payload::write_into(payload, &mut buf).unwrap();
+    buf[..size as usize].to_vec()
+}
+
+fn assert_round_trip(packet: Packet) {
+    let bytes = encode(packet.clone());
+
+    let body = &bytes[1..bytes.len() - 1];
+    assert!(
+        !body.iter().any(|b| matches!(b, 0x3c | 0x3e)),
+        "unescaped delimiter in {:02x?}",
+        bytes
+    );
+
+    assert_eq!(packet, decode(&bytes));
+}
+
+#[test]
+fn round_trip_plain_payload() {
+    assert_round_trip(Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::AmbientSoundControlSet(AncPayload {
+            anc_mode: AncMode::On,
+            focus_on_voice: false,
+            ambiant_level: 1,
+        })),
+    ));
+}
+
+#[test]
+fn round_trip_escaped_payload() {
+    assert_round_trip(Packet::new(
+        1,
+        PacketContent::Command1(PayloadCommand1::InitReply(ProtocolInfo {
+            version: 0x3c3d3e00,
+        })),
+    ));
+}
+
+#[test]
+fn round_trip_escaped_ambiant_level() {
+    for ambiant_level in [0x3c, 0x3d, 0x3e] {
+        assert_round_trip(Packet::new(
+            0,
+            PacketContent::Command1(PayloadCommand1::AmbientSoundControlSet(AncPayload {
+                anc_mode: AncMode::AmbiantMode,
+                focus_on_voice: true,
+                ambiant_level,
+            })),
+        ));
+    }
+}
+
+#[test]
+fn round_trip_escaped_seqnum() {
+    assert_round_trip(Packet::new(
+        0x3e,
+        PacketContent::Command1(PayloadCommand1::InitRequest),
+    ));
+}
+
+#[test]
+fn round_trip_command2() {
+    assert_round_trip(Packet::new(
+        0,
+        PacketContent::Command2(PayloadCommand2::VoiceGuidanceGet),
+    ));
+    for enabled in [true, false] {
+        assert_round_trip(Packet::new(
+            1,
+            PacketContent::Command2(PayloadCommand2::VoiceGuidanceSet(VoiceGuidance { enabled })),
+        ));
+    }
+    assert_round_trip(Packet::new(
+        0,
+        PacketContent::Command2(PayloadCommand2::Unknown {
+            opcode: 0x20,
+            payload: [0x01, 0x3c, 0x02].as_slice().try_into().unwrap(),
+        }),
+    ));
+}
+
+#[test]
+fn round_trip_device_info() {
+    assert_round_trip(Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::DeviceInfoRequest(
+            DeviceInfoType::FirmwareVersion,
+        )),
+    ));
+    for info in [
+        DeviceInfo::ModelName("WH-1000XM4".try_into().unwrap()),
+        DeviceInfo::FirmwareVersion("2.1.0".try_into().unwrap()),
+        DeviceInfo::SeriesAndColor {
+            series: 0x3c,
+            color: 0x01,
+        },
+    ] {
+        assert_round_trip(Packet::new(
+            1,
+            PacketContent::Command1(PayloadCommand1::DeviceInfoReply(info)),
+        ));
+    }
+}
+
+#[test]
+fn decode_firmware_version() {
+    let payload = [0x05, 0x02, 0x03, b'1', b'.', b'2'];
+    match PayloadCommand1::try_from(payload.as_slice()).unwrap() {
+        PayloadCommand1::DeviceInfoReply(DeviceInfo::FirmwareVersion(version)) => {
+            assert_eq!("1.2", version.as_str())
+        }
+        payload => panic!("unexpected payload {:?}", payload),
+    }
+}
+
+#[test]
+fn decode_longest_model_name() {
+    let mut payload = vec![0x05, 0x01, 0xff];
+    payload.resize(payload.len() + 0xff, b'X');
+    match PayloadCommand1::try_from(payload.as_slice()).unwrap() {
+        PayloadCommand1::DeviceInfoReply(DeviceInfo::ModelName(name)) => {
+            assert_eq!(InfoString::CAPACITY, name.as_str().len())
+        }
+        payload => panic!("unexpected payload {:?}", payload),
+    }
+}
+
+#[test]
+fn round_trip_support_functions() {
+    let mut functions = SupportFunctions::default();
+    functions.insert(Feature::Equalizer as u8);
+    functions.insert(0x3c);
+    functions.insert(0xff);
+
+    assert_round_trip(Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::Init2Reply(functions)),
+    ));
+}
+
+#[test]
+fn decode_short_init_reply() {
+    assert_eq!(
+        Ok(PayloadCommand1::InitReply(ProtocolInfo { version: 0 })),
+        PayloadCommand1::try_from([0x01, 0x00, 0x00].as_slice())
+    );
+}
+
+#[test]
+fn capabilities_from_init_replies() {
+    let protocol = [0x01, 0x00, 0x00, 0x40, 0x00, 0x00, 0x01, 0x00];
+    let functions = [0x07, 0x00, 0x03, 0x62, 0x01, 0x51, 0x02, 0xf5, 0x03];
+
+    let (PayloadCommand1::InitReply(protocol), PayloadCommand1::Init2Reply(functions)) = (
+        PayloadCommand1::try_from(protocol.as_slice()).unwrap(),
+        PayloadCommand1::try_from(functions.as_slice()).unwrap(),
+    ) else {
+        panic!("unexpected payloads");
+    };
+
+    let capabilities = Capabilities::new(protocol, functions);
+    assert_eq!(0x00400000, capabilities.protocol_version);
+    assert!(capabilities.supports_anc());
+    assert!(capabilities.supports(Feature::Equalizer));
+    assert!(capabilities.functions.contains(0xf5));
+    assert!(!capabilities.supports(Feature::AmbientSound));
+    assert!(!capabilities.supports(Feature::NcOptimizer));
+}
+
+#[test]
+fn round_trip_equalizer() {
+    assert_round_trip(Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::EqualizerSet(EqualizerPayload {
+            preset: EqPreset::BassBoost,
+            bands: None,
+        })),
+    ));
+    assert_round_trip(Packet::new(
+        1,
+        PacketContent::Command1(PayloadCommand1::EqualizerNotify(EqualizerPayload {
+            preset: EqPreset::Custom1,
+            bands: Some(EqBands {
+                clear_bass: -10,
+                bands: [10, 0, -3, 4, -1],
+            }),
+        })),
+    ));
+}
+
+#[test]
+fn equalizer_level_out_of_range_is_rejected() {
+    let packet = Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::EqualizerSet(EqualizerPayload {
+            preset: EqPreset::Manual,
+            bands: Some(EqBands {
+                clear_bass: 0,
+                bands: [0, 11, 0, 0, 0],
+            }),
+        })),
+    );
+    assert_eq!(
+        Err(Error::OutOfRange {
+            what: "eq band level",
+            value: 11
+        }),
+        packet.write_into(&mut [0u8; 64])
+    );
+
+    let payload = [0x57, 0x01, 0xa0, 0x06, 0x0a, 0x0a, 0x15, 0x0a, 0x0a, 0x0a];
+    assert!(matches!(
+        PayloadCommand1::try_from(payload.as_slice()),
+        Err(Error::OutOfRange { .. })
+    ));
+}
+
+#[test]
+fn round_trip_volume() {
+    assert_round_trip(Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::VolumeGet),
+    ));
+    for level in [0, 15, VolumePayload::MAX] {
+        assert_round_trip(Packet::new(
+            1,
+            PacketContent::Command1(PayloadCommand1::VolumeSet(VolumePayload { level })),
+        ));
+    }
+
+    assert_eq!(
+        Err(Error::OutOfRange {
+            what: "volume",
+            value: 31
+        }),
+        Packet::new(
+            0,
+            PacketContent::Command1(PayloadCommand1::VolumeSet(VolumePayload { level: 31 })),
+        )
+        .write_into(&mut [0u8; 64])
+    );
+}
+
+#[test]
+fn volume_encoding() {
+    assert_eq!(
+        vec![0xa8, 0x20, 0x0c],
+        encode_payload(&PayloadCommand1::VolumeSet(VolumePayload { level: 12 }))
+    );
+
+    assert_eq!(
+        vec![0xa6, 0x20],
+        encode_payload(&PayloadCommand1::VolumeGet)
+    );
+}
+
+#[test]
+fn decode_volume() {
+    let payload = [0xa9, 0x20, 0x1e];
+    assert_eq!(
+        Ok(PayloadCommand1::VolumeNotify(VolumePayload { level: 30 })),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+}
+
+#[test]
+fn round_trip_nc_optimizer() {
+    for content in [
+        PayloadCommand1::NoiseCancellingOptimizerStart(NcOptimizerStart { start: true }),
+        PayloadCommand1::NoiseCancellingOptimizerStatus(NcOptimizerStatus::Analyzing),
+        PayloadCommand1::NoiseCancellingOptimizerStatus(NcOptimizerStatus::Other(0x42)),
+        PayloadCommand1::NoiseCancellingOptimizerStateGet,
+        PayloadCommand1::NoiseCancellingOptimizerStateNotify(NcOptimizerState {
+            personal_optimized: true,
+            atmospheric_pressure: Some(10),
+        }),
+        PayloadCommand1::NoiseCancellingOptimizerStateRet(NcOptimizerState {
+            personal_optimized: false,
+            atmospheric_pressure: None,
+        }),
+    ] {
+        assert_round_trip(Packet::new(0, PacketContent::Command1(content)));
+    }
+}
+
+#[test]
+fn nc_optimizer_encoding() {
+    assert_eq!(
+        vec![0x84, 0x01, 0x00, 0x01],
+        encode_payload(&PayloadCommand1::NoiseCancellingOptimizerStart(
+            NcOptimizerStart { start: true }
+        ))
+    );
+
+    assert_eq!(
+        vec![0x86, 0x01],
+        encode_payload(&PayloadCommand1::NoiseCancellingOptimizerStateGet)
+    );
+}
+
+#[test]
+fn decode_nc_optimizer() {
+    let payload = [0x85, 0x01, 0x00, 0x10];
+    assert_eq!(
+        Ok(PayloadCommand1::NoiseCancellingOptimizerStatus(
+            NcOptimizerStatus::Analyzing
+        )),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+
+    let payload = [0x87, 0x01, 0x01, 0x0a];
+    assert_eq!(
+        Ok(PayloadCommand1::NoiseCancellingOptimizerStateRet(
+            NcOptimizerState {
+                personal_optimized: true,
+                atmospheric_pressure: Some(10),
+            }
+        )),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+}
+
+#[test]
+fn round_trip_touch_sensor() {
+    for content in [
+        PayloadCommand1::TouchSensorGet(TouchSensorType::TouchPanel),
+        PayloadCommand1::TouchSensorSet(TouchSensorPayload::TouchPanel { enabled: false }),
+        PayloadCommand1::TouchSensorNotify(TouchSensorPayload::TouchPanel { enabled: true }),
+    ] {
+        assert_round_trip(Packet::new(0, PacketContent::Command1(content)));
+    }
+}
+
+#[test]
+fn touch_sensor_encoding() {
+    let payload = PayloadCommand1::TouchSensorSet(TouchSensorPayload::TouchPanel { enabled: true });
+    assert_eq!(vec![0xd8, 0xd2, 0x01, 0x01], encode_payload(&payload));
+}
+
+#[test]
+fn decode_touch_sensor() {
+    let payload = [0xd7, 0xd2, 0x01, 0x00];
+    assert_eq!(
+        Ok(PayloadCommand1::TouchSensorRet(
+            TouchSensorPayload::TouchPanel { enabled: false }
+        )),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+}
+
+#[test]
+fn button_modes_encoding() {
+    let payload =
+        PayloadCommand1::AutomaticPowerOffButtonModeSet(AutoPowerOffButtonPayload::ButtonModes {
+            left: ButtonFunction::AmbientSoundControl,
+            right: ButtonFunction::PlaybackControl,
+        });
+    assert_eq!(vec![0xf8, 0x06, 0x02, 0x00, 0x20], encode_payload(&payload));
+}
+
+#[test]
+fn decode_button_modes() {
+    let payload = [0xf7, 0x06, 0x02, 0x20, 0x10];
+    assert_eq!(
+        Ok(PayloadCommand1::AutomaticPowerOffButtonModeRet(
+            AutoPowerOffButtonPayload::ButtonModes {
+                left: ButtonFunction::PlaybackControl,
+                right: ButtonFunction::VoiceAssistant,
+            }
+        )),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+}
+
+#[test]
+fn nc_amb_button_encoding() {
+    let payload = PayloadCommand1::AutomaticPowerOffButtonModeSet(
+        AutoPowerOffButtonPayload::NcAmbButton(NcAmbButtonMode::NoiseCancellingAmbientSound),
+    );
+    assert_eq!(
+        vec![0xf8, 0x03, 0x01, 0x35, 0x01, 0x00, 0x02],
+        encode_payload(&payload)
+    );
+}
+
+#[test]
+fn decode_nc_amb_button() {
+    let payload = [0xf9, 0x03, 0x01, 0x35, 0x01, 0x00, 0x04];
+    assert_eq!(
+        Ok(PayloadCommand1::AutomaticPowerOffButtonModeNotify(
+            AutoPowerOffButtonPayload::NcAmbButton(NcAmbButtonMode::AmbientSoundOff)
+        )),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+}
+
+#[test]
+fn round_trip_upsampling() {
+    assert_round_trip(Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::AudioUpsamplingGet),
+    ));
+    for mode in [
+        UpsamplingMode::Off,
+        UpsamplingMode::On,
+        UpsamplingMode::Auto,
+    ] {
+        assert_round_trip(Packet::new(
+            1,
+            PacketContent::Command1(PayloadCommand1::AudioUpsamplingNotify(UpsamplingPayload {
+                mode,
+            })),
+        ));
+    }
+}
+
+#[test]
+fn upsampling_encoding() {
+    assert_eq!(
+        vec![0xe8, 0x02, 0x00, 0x02],
+        encode_payload(&PayloadCommand1::AudioUpsamplingSet(UpsamplingPayload {
+            mode: UpsamplingMode::Auto,
+        }))
+    );
+
+    assert_eq!(
+        vec![0xe6, 0x02],
+        encode_payload(&PayloadCommand1::AudioUpsamplingGet)
+    );
+}
+
+#[test]
+fn decode_upsampling() {
+    let payload = [0xe7, 0x02, 0x00, 0x01];
+    assert_eq!(
+        Ok(PayloadCommand1::AudioUpsamplingRet(UpsamplingPayload {
+            mode: UpsamplingMode::On,
+        })),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+}
+
+#[test]
+fn round_trip_auto_power_off() {
+    assert_round_trip(Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::AutomaticPowerOffButtonModeGet(
+            AutoPowerOffButtonType::AutoPowerOff,
+        )),
+    ));
+    for auto_power_off in [
+        AutoPowerOff::Off,
+        AutoPowerOff::After5Minutes,
+        AutoPowerOff::After30Minutes,
+        AutoPowerOff::After1Hour,
+        AutoPowerOff::After3Hours,
+        AutoPowerOff::WhenTakenOff,
+    ] {
+        assert_round_trip(Packet::new(
+            1,
+            PacketContent::Command1(PayloadCommand1::AutomaticPowerOffButtonModeSet(
+                AutoPowerOffButtonPayload::AutoPowerOff(auto_power_off),
+            )),
+        ));
+    }
+    assert_round_trip(Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::AutomaticPowerOffButtonModeNotify(
+            AutoPowerOffButtonPayload::ButtonModes {
+                left: ButtonFunction::AmbientSoundControl,
+                right: ButtonFunction::Off,
+            },
+        )),
+    ));
+}
+
+#[test]
+fn auto_power_off_encoding() {
+    let payload = PayloadCommand1::AutomaticPowerOffButtonModeSet(
+        AutoPowerOffButtonPayload::AutoPowerOff(AutoPowerOff::Off),
+    );
+    assert_eq!(vec![0xf8, 0x04, 0x01, 0x11, 0x00], encode_payload(&payload));
+}
+
+#[test]
+fn round_trip_speak_to_chat() {
+    for content in [
+        PayloadCommand1::SpeakToChatConfigGet,
+        PayloadCommand1::SpeakToChatConfigSet(SpeakToChatConfig {
+            sensitivity: SpeakToChatSensitivity::Low,
+            voice_focus: true,
+            timeout: SpeakToChatTimeout::Short,
+        }),
+        PayloadCommand1::SpeakToChatConfigNotify(SpeakToChatConfig {
+            sensitivity: SpeakToChatSensitivity::Auto,
+            voice_focus: false,
+            timeout: SpeakToChatTimeout::Off,
+        }),
+        PayloadCommand1::AutomaticPowerOffButtonModeSet(AutoPowerOffButtonPayload::SpeakToChat {
+            enabled: true,
+        }),
+        PayloadCommand1::AutomaticPowerOffButtonModeRet(AutoPowerOffButtonPayload::SpeakToChat {
+            enabled: false,
+        }),
+    ] {
+        assert_round_trip(Packet::new(0, PacketContent::Command1(content)));
+    }
+}
+
+#[test]
+fn speak_to_chat_encoding() {
+    let payload = PayloadCommand1::SpeakToChatConfigSet(SpeakToChatConfig {
+        sensitivity: SpeakToChatSensitivity::High,
+        voice_focus: true,
+        timeout: SpeakToChatTimeout::Long,
+    });
+    assert_eq!(
+        vec![0xfc, 0x05, 0x00, 0x01, 0x01, 0x02],
+        encode_payload(&payload)
+    );
+
+    let payload =
+        PayloadCommand1::AutomaticPowerOffButtonModeSet(AutoPowerOffButtonPayload::SpeakToChat {
+            enabled: true,
+        });
+    assert_eq!(vec![0xf8, 0x0c, 0x00, 0x01], encode_payload(&payload));
+}
+
+#[test]
+fn decode_speak_to_chat_config() {
+    let payload = [0xfb, 0x05, 0x00, 0x00, 0x00, 0x01];
+    assert_eq!(
+        Ok(PayloadCommand1::SpeakToChatConfigRet(SpeakToChatConfig {
+            sensitivity: SpeakToChatSensitivity::Auto,
+            voice_focus: false,
+            timeout: SpeakToChatTimeout::Standard,
+        })),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+}
+
+#[test]
+fn round_trip_sound_position() {
+    for content in [
+        PayloadCommand1::SoundPositionOrModeGet(SoundPositionType::Position),
+        PayloadCommand1::SoundPositionOrModeGet(SoundPositionType::Surround),
+        PayloadCommand1::SoundPositionOrModeRet(SoundPositionPayload::Position(
+            SoundPosition::RearLeft,
+        )),
+        PayloadCommand1::SoundPositionOrModeSet(SoundPositionPayload::Surround(
+            SurroundMode::ConcertHall,
+        )),
+        PayloadCommand1::SoundPositionOrModeNotify(SoundPositionPayload::Position(
+            SoundPosition::Off,
+        )),
+    ] {
+        assert_round_trip(Packet::new(0, PacketContent::Command1(content)));
+    }
+}
+
+#[test]
+fn sound_position_encoding() {
+    assert_eq!(
+        vec![0x48, 0x02, 0x12],
+        encode_payload(&PayloadCommand1::SoundPositionOrModeSet(
+            SoundPositionPayload::Position(SoundPosition::RearRight,)
+        ))
+    );
+
+    assert_eq!(
+        vec![0x46, 0x01],
+        encode_payload(&PayloadCommand1::SoundPositionOrModeGet(
+            SoundPositionType::Surround
+        ))
+    );
+}
+
+#[test]
+fn decode_sound_position() {
+    let payload = [0x47, 0x01, 0x02];
+    assert_eq!(
+        Ok(PayloadCommand1::SoundPositionOrModeRet(
+            SoundPositionPayload::Surround(SurroundMode::Arena)
+        )),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+
+    let payload = [0x49, 0x02, 0x03];
+    assert_eq!(
+        Ok(PayloadCommand1::SoundPositionOrModeNotify(
+            SoundPositionPayload::Position(SoundPosition::Front)
+        )),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+}
+
+#[test]
+fn round_trip_audio_codec() {
+    for content in [
+        PayloadCommand1::AudioCodecRequest,
+        PayloadCommand1::AudioCodecReply(AudioCodec::Ldac),
+        PayloadCommand1::AudioCodecNotify(AudioCodec::Aac),
+        PayloadCommand1::AudioCodecNotify(AudioCodec::Unsettled),
+        PayloadCommand1::AudioCodecNotify(AudioCodec::Unknown(0x42)),
+    ] {
+        assert_round_trip(Packet::new(0, PacketContent::Command1(content)));
+    }
+}
+
+#[test]
+fn audio_codec_encoding() {
+    assert_eq!(
+        vec![0x18, 0x00],
+        encode_payload(&PayloadCommand1::AudioCodecRequest)
+    );
+
+    assert_eq!(
+        vec![0x1b, 0x00, 0x21],
+        encode_payload(&PayloadCommand1::AudioCodecNotify(AudioCodec::AptXHd))
+    );
+}
+
+#[test]
+fn decode_audio_codec() {
+    let payload = [0x19, 0x00, 0x10];
+    assert_eq!(
+        Ok(PayloadCommand1::AudioCodecReply(AudioCodec::Ldac)),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+
+    let payload = [0x1b, 0x00, 0x02];
+    assert_eq!(
+        Ok(PayloadCommand1::AudioCodecNotify(AudioCodec::Aac)),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+
+    let payload = [0x1b, 0x00, 0x42];
+    assert_eq!(
+        Ok(PayloadCommand1::AudioCodecNotify(AudioCodec::Unknown(0x42))),
+        PayloadCommand1::try_from(payload.as_slice())
+    );
+}
+
+#[test]
+fn power_off_encoding() {
+    assert_eq!(
+        vec![0x22, 0x00, 0x01],
+        encode_payload(&PayloadCommand1::PowerOff)
+    );
+
+    assert_round_trip(Packet::new(
+        1,
+        PacketContent::Command1(PayloadCommand1::PowerOff),
+    ));
+}
+
+#[test]
+fn round_trip_json() {
+    assert_round_trip(Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::JsonGet(0x01)),
+    ));
+
+    #[cfg(feature = "alloc")]
+    assert_round_trip(Packet::new(
+        1,
+        PacketContent::Command1(PayloadCommand1::JsonRet(sony_protocol::v1::JsonPayload {
+            json_type: 0x01,
+            text: r#"{"name":"<=>","values":[1,2,3]}"#.into(),
+        })),
+    ));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn parse_json() {
+    let payload = sony_protocol::v1::JsonPayload {
+        json_type: 0x01,
+        text: r#"{"values":[1,2,3]}"#.into(),
+    };
+    assert_eq!(
+        serde_json::json!({ "values": [1, 2, 3] }),
+        payload.parse().unwrap()
+    );
+    assert!(sony_protocol::v1::JsonPayload {
+        json_type: 0x01,
+        text: "{".into(),
+    }
+    .parse()
+    .is_err());
+}
+
+#[test]
+fn escape_encoding() {
+    let bytes = encode(Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::InitReply(ProtocolInfo {
+            version: 0x3c3d3e00,
+        })),
+    ));
+
+    assert_eq!(
+        &[0x3e, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x06, 0x01, 0x00],
+        &bytes[..9]
+    );
+    assert_eq!(&[0x3d, 0x2c, 0x3d, 0x2d, 0x3d, 0x2e, 0x00], &bytes[9..16]);
+}
+
+#[test]
+fn encoding_into_small_buffer_fails() {
+    let packet = Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::InitReply(ProtocolInfo {
+            version: 0x3c3d3e00,
+        })),
+    );
+    let size = encode(packet.clone()).len();
+
+    for len in 0..size {
+        let mut buf = vec![0u8; len];
+        assert!(
+            matches!(
+                packet.clone().write_into(&mut buf),
+                Err(Error::BufferTooSmall { .. })
+            ),
+            "buffer of {} bytes",
+            len
+        );
+    }
+
+    let mut buf = vec![0u8; size];
+    assert_eq!(Ok(size), packet.write_into(&mut buf));
+}
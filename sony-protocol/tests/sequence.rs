@@ -0,0 +1,335 @@
+use sony_protocol::{
+    v1::{AncMode, AncPayload, DataType, Packet, PacketContent, PayloadCommand1},
+    Device, Error, PacketId, State, Timestamp,
+};
+
+const NOW: Timestamp = Timestamp::from_millis(0);
+
+fn encode(packet: Packet) -> Vec<u8> {
+    let mut buf = [0u8; 1024];
+    let size = packet.write_into(&mut buf).unwrap();
+    buf[..size].to_vec()
+}
+
+fn notify(seqnum: u8, ambiant_level: u8) -> Packet {
+    Packet::new(
+        seqnum,
+        PacketContent::Command1(PayloadCommand1::AmbientSoundControlNotify(AncPayload {
+            anc_mode: AncMode::AmbiantMode,
+            focus_on_voice: false,
+            ambiant_level,
+        })),
+    )
+}
+
+fn ack(seqnum: u8) -> Vec<u8> {
+    encode(Packet::new(seqnum, PacketContent::Ack))
+}
+
+/// Poll the device until it is waiting, returning the sent and received packets.
+fn poll_all(device: &mut Device) -> (Vec<Vec<u8>>, Vec<Packet>) {
+    let (sent, received, acked) = poll_acked(device);
+    assert!(acked.is_empty());
+    (sent, received)
+}
+
+/// Poll the device until it is waiting, also returning the acknowledged packets.
+fn poll_acked(device: &mut Device) -> (Vec<Vec<u8>>, Vec<Packet>, Vec<PacketId>) {
+    let mut sent = Vec::new();
+    let mut received = Vec::new();
+    let mut acked = Vec::new();
+    loop {
+        match device.poll(NOW).unwrap() {
+            State::WaitingPacket(_) => return (sent, received, acked),
+            State::ReceivedPacket(p) => received.push(p),
+            State::SendPacket(p, _) => sent.push(p.to_vec()),
+            State::PacketAcked(id) => acked.push(id),
+            state => panic!("unexpected state {:?}", state),
+        }
+    }
+}
+
+#[test]
+fn retransmitted_packet_is_acked_but_not_received_again() {
+    let mut device = Device::default();
+
+    device.received_packet(&encode(notify(0, 5))).unwrap();
+    let (first_acks, received) = poll_all(&mut device);
+    assert_eq!(vec![ack(1)], first_acks);
+    assert_eq!(vec![notify(0, 5)], received);
+
+    device.received_packet(&encode(notify(0, 5))).unwrap();
+    let (second_acks, received) = poll_all(&mut device);
+    assert_eq!(first_acks, second_acks);
+    assert!(received.is_empty());
+}
+
+#[test]
+fn next_packet_is_received() {
+    let mut device = Device::default();
+
+    device.received_packet(&encode(notify(0, 5))).unwrap();
+    device.received_packet(&encode(notify(1, 6))).unwrap();
+    device.received_packet(&encode(notify(0, 7))).unwrap();
+
+    let (acks, received) = poll_all(&mut device);
+    assert_eq!(vec![ack(1), ack(0), ack(1)], acks);
+    assert_eq!(vec![notify(0, 5), notify(1, 6), notify(0, 7)], received);
+}
+
+#[test]
+fn ack_without_pending_packet_is_ignored() {
+    let mut device = Device::default();
+
+    device.received_packet(&ack(1)).unwrap();
+    let (sent, received) = poll_all(&mut device);
+    assert!(sent.is_empty());
+    assert!(received.is_empty());
+}
+
+#[test]
+fn duplicated_ack_is_ignored() {
+    let mut device = Device::default();
+    let id = device
+        .send_packet(PacketContent::Command1(PayloadCommand1::InitRequest))
+        .unwrap();
+    let (sent, _) = poll_all(&mut device);
+    assert_eq!(1, sent.len());
+
+    device.received_packet(&ack(1)).unwrap();
+    device.received_packet(&ack(1)).unwrap();
+    let (_, received, acked) = poll_acked(&mut device);
+    assert!(received.is_empty());
+    assert_eq!(vec![id], acked);
+}
+
+#[test]
+fn ack_for_previous_packet_is_ignored() {
+    let mut device = Device::default();
+    device
+        .send_packet(PacketContent::Command1(PayloadCommand1::InitRequest))
+        .unwrap();
+    poll_all(&mut device);
+    device.received_packet(&ack(1)).unwrap();
+    poll_acked(&mut device);
+
+    let id = device
+        .send_packet(PacketContent::Command1(
+            PayloadCommand1::AmbientSoundControlGet,
+        ))
+        .unwrap();
+    poll_all(&mut device);
+
+    // Late retransmission of the first ACK.
+    device.received_packet(&ack(1)).unwrap();
+    let (_, received, acked) = poll_acked(&mut device);
+    assert!(received.is_empty());
+    assert!(acked.is_empty());
+
+    device.received_packet(&ack(0)).unwrap();
+    let (_, _, acked) = poll_acked(&mut device);
+    assert_eq!(vec![id], acked);
+}
+
+#[test]
+fn queued_packets_are_sent_one_at_a_time() {
+    let mut device = Device::default();
+    let first = device
+        .send_packet(PacketContent::Command1(PayloadCommand1::InitRequest))
+        .unwrap();
+    let second = device
+        .send_packet(PacketContent::Command1(
+            PayloadCommand1::AmbientSoundControlGet,
+        ))
+        .unwrap();
+    assert_ne!(first, second);
+
+    let (sent, _) = poll_all(&mut device);
+    assert_eq!(
+        vec![encode(Packet::new(
+            0,
+            PacketContent::Command1(PayloadCommand1::InitRequest)
+        ))],
+        sent
+    );
+
+    device.received_packet(&ack(1)).unwrap();
+    let (sent, _, acked) = poll_acked(&mut device);
+    assert_eq!(vec![first], acked);
+    assert_eq!(
+        vec![encode(Packet::new(
+            1,
+            PacketContent::Command1(PayloadCommand1::AmbientSoundControlGet)
+        ))],
+        sent
+    );
+
+    device.received_packet(&ack(0)).unwrap();
+    let (sent, _, acked) = poll_acked(&mut device);
+    assert_eq!(vec![second], acked);
+    assert!(sent.is_empty());
+}
+
+#[test]
+fn queue_is_bounded() {
+    let mut device = Device::default();
+    while !device.is_queue_full() {
+        device
+            .send_packet(PacketContent::Command1(PayloadCommand1::InitRequest))
+            .unwrap();
+    }
+
+    assert_eq!(
+        Err(Error::QueueFull),
+        device.send_packet(PacketContent::Command1(PayloadCommand1::InitRequest))
+    );
+}
+
+#[test]
+fn packet_failing_to_encode_is_reported() {
+    let mut device = Device::default();
+    let failing = device
+        .send_packet(PacketContent::Command1(PayloadCommand1::SomethingGet))
+        .unwrap();
+    device
+        .send_packet(PacketContent::Command1(PayloadCommand1::InitRequest))
+        .unwrap();
+
+    assert!(matches!(device.poll(NOW).unwrap(), State::PacketFailed(id) if id == failing));
+    assert!(matches!(device.poll(NOW).unwrap(), State::SendPacket(..)));
+}
+
+#[test]
+fn shot_packet_is_not_acked() {
+    let mut device = Device::default();
+    let packet = Packet::new(
+        0,
+        PacketContent::ShotCommand1(PayloadCommand1::AmbientSoundControlGet),
+    );
+
+    device.received_packet(&encode(packet.clone())).unwrap();
+    let (sent, received) = poll_all(&mut device);
+    assert!(sent.is_empty());
+    assert_eq!(vec![packet], received);
+}
+
+#[test]
+fn unknown_data_type_is_acked_and_received_raw() {
+    let mut device = Device::default();
+    let packet = Packet::new(
+        0,
+        PacketContent::Raw {
+            data_type: DataType::Unknown(0x42),
+            payload: [0x01, 0x02].as_slice().try_into().unwrap(),
+        },
+    );
+
+    device.received_packet(&encode(packet.clone())).unwrap();
+    let (sent, received) = poll_all(&mut device);
+    assert_eq!(vec![ack(1)], sent);
+    assert_eq!(vec![packet], received);
+}
+
+#[test]
+fn sent_shot_packet_does_not_wait_for_ack() {
+    let mut device = Device::default();
+    let shot = device
+        .send_packet(PacketContent::ShotCommand1(
+            PayloadCommand1::AmbientSoundControlGet,
+        ))
+        .unwrap();
+    device
+        .send_packet(PacketContent::Command1(PayloadCommand1::InitRequest))
+        .unwrap();
+
+    assert!(matches!(device.poll(NOW).unwrap(), State::SendPacket(..)));
+    assert!(matches!(device.poll(NOW).unwrap(), State::PacketAcked(id) if id == shot));
+    assert!(matches!(device.poll(NOW).unwrap(), State::SendPacket(..)));
+}
+
+#[test]
+fn sent_packet_is_identified_while_others_are_queued() {
+    let mut device = Device::default();
+    let power_off = device
+        .send_packet(PacketContent::Command1(PayloadCommand1::PowerOff))
+        .unwrap();
+    let next = device
+        .send_packet(PacketContent::Command1(PayloadCommand1::InitRequest))
+        .unwrap();
+
+    assert!(matches!(
+        device.poll(NOW).unwrap(),
+        State::SendPacket(_, Some(id)) if id == power_off
+    ));
+    assert!(matches!(device.poll(NOW).unwrap(), State::WaitingPacket(_)));
+
+    device.received_packet(&ack(1)).unwrap();
+    assert!(matches!(device.poll(NOW).unwrap(), State::PacketAcked(id) if id == power_off));
+    assert!(matches!(
+        device.poll(NOW).unwrap(),
+        State::SendPacket(_, Some(id)) if id == next
+    ));
+}
+
+#[test]
+fn ack_sent_before_queued_packet_is_not_identified() {
+    let mut device = Device::default();
+    let power_off = device
+        .send_packet(PacketContent::Command1(PayloadCommand1::PowerOff))
+        .unwrap();
+    device.received_packet(&encode(notify(0, 5))).unwrap();
+
+    match device.poll(NOW).unwrap() {
+        State::SendPacket(p, None) => assert_eq!(ack(1), p),
+        state => panic!("unexpected state {:?}", state),
+    }
+    assert!(matches!(
+        device.poll(NOW).unwrap(),
+        State::ReceivedPacket(_)
+    ));
+    assert!(matches!(
+        device.poll(NOW).unwrap(),
+        State::SendPacket(_, Some(id)) if id == power_off
+    ));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn packet_larger_than_initial_buffer_is_sent() {
+    let packet =
+        PacketContent::Command1(PayloadCommand1::JsonRet(sony_protocol::v1::JsonPayload {
+            json_type: 0x01,
+            text: "0".repeat(4000),
+        }));
+
+    let mut device = Device::default();
+    device.send_packet(packet).unwrap();
+
+    let (sent, received) = poll_all(&mut device);
+    assert_eq!(1, sent.len());
+    assert!(sent[0].len() > 4000);
+    assert!(received.is_empty());
+}
+
+#[test]
+fn read_buffer_full_of_stray_acks_is_dropped_in_one_poll() {
+    let mut device = Device::default();
+
+    // Grow the read buffer as much as possible with a frame that never ends.
+    let mut oversized = vec![0x3e];
+    oversized.extend(vec![0x00; 128 * 1024]);
+    let mut bytes = oversized.as_slice();
+    while !bytes.is_empty() {
+        let consumed = device.received_packet(bytes).unwrap();
+        bytes = &bytes[consumed..];
+        poll_all(&mut device);
+    }
+
+    let acks = ack(0).repeat(64 * 1024 / ack(0).len());
+    device.received_packet(&acks).unwrap();
+
+    assert!(matches!(
+        device.poll(NOW).unwrap(),
+        State::WaitingPacket(None)
+    ));
+}
@@ -0,0 +1,187 @@
+use sony_protocol::{
+    v1::{AncMode, AncPayload, Packet, PacketContent, PayloadCommand1, ProtocolInfo},
+    Device, State, Timestamp,
+};
+
+const NOW: Timestamp = Timestamp::from_millis(0);
+
+fn encode(packet: Packet) -> Vec<u8> {
+    let mut buf = [0u8; 1024];
+    let size = packet.write_into(&mut buf).unwrap();
+    buf[..size].to_vec()
+}
+
+fn escaped_packet(seqnum: u8) -> Packet {
+    Packet::new(
+        seqnum,
+        PacketContent::Command1(PayloadCommand1::InitReply(ProtocolInfo {
+            version: 0x3c3d3e00,
+        })),
+    )
+}
+
+fn anc_packet(seqnum: u8) -> Packet {
+    Packet::new(
+        seqnum,
+        PacketContent::Command1(PayloadCommand1::AmbientSoundControlNotify(AncPayload {
+            anc_mode: AncMode::AmbiantMode,
+            focus_on_voice: false,
+            ambiant_level: 12,
+        })),
+    )
+}
+
+/// Poll the device until it is waiting, returning the received packets.
+fn poll_packets(device: &mut Device) -> Vec<Packet> {
+    let mut packets = Vec::new();
+    loop {
+        match device.poll(NOW).unwrap() {
+            State::WaitingPacket(_) => return packets,
+            State::ReceivedPacket(p) => packets.push(p),
+            State::SendPacket(..) => (),
+            state => panic!("unexpected state {:?}", state),
+        }
+    }
+}
+
+/// Feed all the bytes to the device, polling whenever its buffer is full.
+fn feed(device: &mut Device, mut bytes: &[u8]) -> Vec<Packet> {
+    let mut packets = Vec::new();
+    while !bytes.is_empty() {
+        let consumed = device.received_packet(bytes).unwrap();
+        bytes = &bytes[consumed..];
+        packets.extend(poll_packets(device));
+    }
+    packets
+}
+
+#[test]
+fn byte_by_byte() {
+    let packet = escaped_packet(0);
+    let bytes = encode(packet.clone());
+
+    let mut device = Device::default();
+    for (i, byte) in bytes.iter().enumerate() {
+        let packets = feed(&mut device, &[*byte]);
+        if i + 1 < bytes.len() {
+            assert!(packets.is_empty());
+        } else {
+            assert_eq!(vec![packet.clone()], packets);
+        }
+    }
+}
+
+#[test]
+fn split_after_escape() {
+    let packet = escaped_packet(1);
+    let bytes = encode(packet.clone());
+    let escape = bytes.iter().position(|b| *b == 0x3d).unwrap();
+
+    let mut device = Device::default();
+    assert!(feed(&mut device, &bytes[..=escape]).is_empty());
+    assert_eq!(vec![packet], feed(&mut device, &bytes[escape + 1..]));
+}
+
+#[test]
+fn several_frames_in_one_read() {
+    let mut bytes = encode(anc_packet(0));
+    bytes.extend(encode(escaped_packet(1)));
+
+    let mut device = Device::default();
+    assert_eq!(
+        vec![anc_packet(0), escaped_packet(1)],
+        feed(&mut device, &bytes)
+    );
+}
+
+#[test]
+fn garbage_before_frame() {
+    let mut bytes = vec![0x00, 0x12, 0x3c, 0x42];
+    bytes.extend(encode(anc_packet(0)));
+
+    let mut device = Device::default();
+    assert_eq!(vec![anc_packet(0)], feed(&mut device, &bytes));
+}
+
+#[test]
+fn truncated_frame_is_skipped() {
+    let first = encode(anc_packet(0));
+    let mut bytes = first[..first.len() / 2].to_vec();
+    bytes.extend(encode(anc_packet(1)));
+
+    let mut device = Device::default();
+    assert_eq!(vec![anc_packet(1)], feed(&mut device, &bytes));
+}
+
+#[test]
+fn corrupted_frame_is_skipped() {
+    let mut bytes = encode(anc_packet(0));
+    bytes[8] ^= 0x01;
+    bytes.extend(encode(anc_packet(1)));
+
+    let mut device = Device::default();
+    assert_eq!(vec![anc_packet(1)], feed(&mut device, &bytes));
+}
+
+#[test]
+fn oversized_frame_is_dropped() {
+    let mut bytes = vec![0x3e];
+    bytes.extend(vec![0x00; 128 * 1024]);
+    bytes.extend(encode(anc_packet(0)));
+
+    let mut device = Device::default();
+    assert_eq!(vec![anc_packet(0)], feed(&mut device, &bytes));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn frame_larger_than_initial_buffer() {
+    let packet = Packet::new(
+        0,
+        PacketContent::Command1(PayloadCommand1::JsonRet(sony_protocol::v1::JsonPayload {
+            json_type: 0x01,
+            text: "[".to_owned() + &"0,".repeat(2000) + "0]",
+        })),
+    );
+    let mut buf = vec![0u8; 8192];
+    let size = packet.write_into(&mut buf).unwrap();
+
+    let mut device = Device::default();
+    assert_eq!(vec![packet], feed(&mut device, &buf[..size]));
+}
+
+#[test]
+fn long_session() {
+    let mut bytes = Vec::new();
+    let mut expected = Vec::new();
+    for i in 0..1000 {
+        let packet = if i % 2 == 0 {
+            anc_packet(0)
+        } else {
+            escaped_packet(1)
+        };
+        bytes.extend(encode(packet.clone()));
+        expected.push(packet);
+    }
+
+    let mut device = Device::default();
+    let mut packets = Vec::new();
+    for chunk in bytes.chunks(77) {
+        packets.extend(feed(&mut device, chunk));
+    }
+
+    assert_eq!(expected, packets);
+}
+
+#[test]
+fn partial_frame_does_not_block_sending() {
+    let bytes = encode(anc_packet(0));
+
+    let mut device = Device::default();
+    device.received_packet(&bytes[..5]).unwrap();
+    device
+        .send_packet(PacketContent::Command1(PayloadCommand1::InitRequest))
+        .unwrap();
+
+    assert!(matches!(device.poll(NOW).unwrap(), State::SendPacket(..)));
+}
@@ -16,7 +16,7 @@ impl Index<usize> for DeviceStream {
     type Output = UiDevice;
 
     fn index(&self, index: usize) -> &Self::Output {
-        return &self.devices[index].1;
+        &self.devices[index].1
     }
 }
 
@@ -33,12 +33,22 @@ impl DeviceStream {
     }
 
     fn handle_stream_event(device: &mut UiDevice, packet: Packet) -> anyhow::Result<()> {
-        match packet.content {
-            PacketContent::Command1(c) => match c {
+        if let PacketContent::Command1(c) = packet.content {
+            match c {
                 PayloadCommand1::AmbientSoundControlRet(n)
                 | PayloadCommand1::AmbientSoundControlNotify(n) => {
                     device.anc_mode = Some(n);
                 }
+                PayloadCommand1::EqualizerRet(e) | PayloadCommand1::EqualizerNotify(e) => {
+                    device.equalizer = Some(e);
+                }
+                PayloadCommand1::AudioUpsamplingRet(u)
+                | PayloadCommand1::AudioUpsamplingNotify(u) => {
+                    device.upsampling = Some(u.mode);
+                }
+                PayloadCommand1::AudioCodecReply(c) | PayloadCommand1::AudioCodecNotify(c) => {
+                    device.codec = Some(c);
+                }
                 PayloadCommand1::BatteryLevelReply(b) | PayloadCommand1::BatteryLevelNotify(b) => {
                     match b {
                         sony_protocol::v1::BatteryState::Single {
@@ -61,8 +71,7 @@ impl DeviceStream {
                     }
                 }
                 _ => (),
-            },
-            _ => (),
+            }
         }
         Ok(())
     }
@@ -92,6 +101,9 @@ impl tokio_stream::Stream for DeviceStream {
                                     anc_mode: None,
                                     battery_device: None,
                                     battery_case: None,
+                                    equalizer: None,
+                                    upsampling: None,
+                                    codec: None,
                                 },
                             ));
                         }
@@ -104,6 +116,21 @@ impl tokio_stream::Stream for DeviceStream {
                                 .await
                                 .unwrap();
 
+                            d.as_ref()
+                                .send(PacketContent::Command1(PayloadCommand1::EqualizerGet))
+                                .await
+                                .unwrap();
+
+                            d.as_ref()
+                                .send(PacketContent::Command1(PayloadCommand1::AudioUpsamplingGet))
+                                .await
+                                .unwrap();
+
+                            d.as_ref()
+                                .send(PacketContent::Command1(PayloadCommand1::AudioCodecRequest))
+                                .await
+                                .unwrap();
+
                             d.as_ref()
                                 .send(PacketContent::Command1(
                                     PayloadCommand1::BatteryLevelRequest(
@@ -140,10 +167,8 @@ impl tokio_stream::Stream for DeviceStream {
             }
         }
 
-        let mut iter = thiz.devices.iter_mut();
-
         let mut deletable = None;
-        while let Some((r, d)) = iter.next() {
+        for (r, d) in thiz.devices.iter_mut() {
             if let Poll::Ready(r) = r.poll_next_unpin(cx) {
                 if let Some(r) = r {
                     Self::handle_stream_event(d, r?)?;
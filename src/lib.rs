@@ -1,3 +1,4 @@
+use anyhow::Context;
 use bluer::{
     agent::Agent,
     rfcomm::{Profile, Role, Stream},
@@ -5,10 +6,14 @@ use bluer::{
 };
 use futures::StreamExt;
 pub use sony_device::SonyDevice;
-use sony_protocol::v1::{PacketContent, PayloadCommand1};
+use sony_protocol::v1::{
+    Capabilities, DeviceInfo, DeviceInfoType, PacketContent, PayloadCommand1, SupportFunctions,
+};
+use std::time::Duration;
 use tokio::{
     sync::mpsc::{self, Receiver, Sender},
     task::JoinHandle,
+    time,
 };
 use tracing::{error, warn};
 
@@ -18,6 +23,9 @@ mod sony_device;
 pub struct Device {
     address: Address,
     name: String,
+    model: Option<String>,
+    firmware_version: Option<String>,
+    capabilities: Capabilities,
     sony_device: SonyDevice,
 }
 
@@ -25,6 +33,9 @@ impl std::fmt::Debug for Device {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Device")
             .field("address", &self.address)
+            .field("model", &self.model)
+            .field("firmware_version", &self.firmware_version)
+            .field("capabilities", &self.capabilities)
             .finish()
     }
 }
@@ -37,11 +48,26 @@ impl Device {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    /// Model name reported by the headset, if it answered.
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    /// Firmware version reported by the headset, if it answered.
+    pub fn firmware_version(&self) -> Option<&str> {
+        self.firmware_version.as_deref()
+    }
+
+    /// Functions supported by the headset, to only offer the controls it has.
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
 }
 
 impl AsRef<SonyDevice> for Device {
     fn as_ref(&self) -> &SonyDevice {
-        return &self.sony_device;
+        &self.sony_device
     }
 }
 
@@ -53,6 +79,7 @@ pub enum DeviceEvent {
 
 pub struct DeviceExplorer {
     pub device_stream: Receiver<DeviceEvent>,
+    #[allow(dead_code)]
     join_handle: JoinHandle<()>,
 }
 
@@ -125,9 +152,9 @@ async fn run_loop(sender: Sender<DeviceEvent>) -> anyhow::Result<()> {
                     let channel = r.accept().unwrap();
                     let sender = sender.clone();
                     tokio::spawn(async move {
-                        match start_communication(channel).await {
+                        match start_communication(channel, addr, name).await {
                             Ok(device) => {
-                                 _ = sender.send(DeviceEvent::DeviceAdded(Device { address: addr, name: name, sony_device: device })).await;
+                                 _ = sender.send(DeviceEvent::DeviceAdded(device)).await;
                             }
                             Err(e) => error!("failed to connect to device : {}", e),
                         }
@@ -138,7 +165,11 @@ async fn run_loop(sender: Sender<DeviceEvent>) -> anyhow::Result<()> {
     }
 }
 
-async fn start_communication(channel: Stream) -> anyhow::Result<SonyDevice> {
+async fn start_communication(
+    channel: Stream,
+    address: Address,
+    name: String,
+) -> anyhow::Result<Device> {
     let (mut device, run_loop) = SonyDevice::new(channel);
 
     tokio::spawn(async move {
@@ -151,7 +182,72 @@ async fn start_communication(channel: Stream) -> anyhow::Result<SonyDevice> {
         .send(PacketContent::Command1(PayloadCommand1::InitRequest))
         .await?;
 
-    _ = device.packets_receiver.recv().await?;
+    let protocol = device.wait_for(|content| match content {
+        PacketContent::Command1(PayloadCommand1::InitReply(protocol)) => Some(protocol),
+        _ => None,
+    });
+    let protocol = time::timeout(Duration::from_secs(2), protocol)
+        .await
+        .context("no init reply received")??;
 
-    Ok(device)
+    device
+        .send(PacketContent::Command1(PayloadCommand1::Init2Request))
+        .await?;
+    let functions = device.wait_for(|content| match content {
+        PacketContent::Command1(PayloadCommand1::Init2Reply(functions)) => Some(functions),
+        _ => None,
+    });
+    let functions = match time::timeout(Duration::from_secs(2), functions).await {
+        Ok(functions) => functions?,
+        Err(_) => {
+            warn!("no support function list received");
+            SupportFunctions::default()
+        }
+    };
+
+    let model = match query_device_info(&mut device, DeviceInfoType::ModelName).await {
+        Ok(DeviceInfo::ModelName(model)) => Some(model.as_str().to_owned()),
+        Ok(_) => None,
+        Err(e) => {
+            warn!("failed to get model name : {}", e);
+            None
+        }
+    };
+    let firmware_version =
+        match query_device_info(&mut device, DeviceInfoType::FirmwareVersion).await {
+            Ok(DeviceInfo::FirmwareVersion(version)) => Some(version.as_str().to_owned()),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("failed to get firmware version : {}", e);
+                None
+            }
+        };
+
+    Ok(Device {
+        address,
+        name,
+        model,
+        firmware_version,
+        capabilities: Capabilities::new(protocol, functions),
+        sony_device: device,
+    })
+}
+
+async fn query_device_info(
+    device: &mut SonyDevice,
+    info_type: DeviceInfoType,
+) -> anyhow::Result<DeviceInfo> {
+    device
+        .request(
+            PacketContent::Command1(PayloadCommand1::DeviceInfoRequest(info_type)),
+            |content| match content {
+                PacketContent::Command1(PayloadCommand1::DeviceInfoReply(info))
+                    if info.info_type() == info_type =>
+                {
+                    Some(info)
+                }
+                _ => None,
+            },
+        )
+        .await
 }
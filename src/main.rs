@@ -2,7 +2,10 @@ use std::{io::stdout, time::Duration};
 
 use bluer::Address;
 use device_stream::DeviceStream;
-use sony_protocol::v1::{AncMode, AncPayload, PacketContent, PayloadCommand1};
+use sony_protocol::v1::{
+    AncMode, AncPayload, AudioCodec, EqPreset, EqualizerPayload, PacketContent, PayloadCommand1,
+    UpsamplingMode, UpsamplingPayload,
+};
 use sony_rs::Device;
 use tokio_stream::StreamExt;
 
@@ -35,6 +38,9 @@ pub struct UiDevice {
     anc_mode: Option<AncPayload>,
     battery_device: Option<UiDeviceBattery>,
     battery_case: Option<u8>,
+    equalizer: Option<EqualizerPayload>,
+    upsampling: Option<UpsamplingMode>,
+    codec: Option<AudioCodec>,
 }
 
 struct App {
@@ -66,13 +72,13 @@ impl App {
 
         while !self.quit {
             terminal.draw(|frame| self.draw(frame))?;
-            let _ = tokio::select! {
-                Some(event) = self.stream.next() => (),
+            tokio::select! {
+                Some(_) = self.stream.next() => (),
                 Some(evt) = reader.next() => {
                     self.on_crossterm_event(evt.unwrap()).await?;
                 }
                 _ = ticker.tick() => ()
-            };
+            }
         }
 
         stdout().execute(LeaveAlternateScreen)?;
@@ -82,61 +88,104 @@ impl App {
 
     async fn on_crossterm_event(&mut self, event: CrosstermEvent) -> anyhow::Result<()> {
         match event {
-            CrosstermEvent::Key(key) => {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char('q') => {
-                            self.quit = true;
+            CrosstermEvent::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Char('q') => {
+                    self.quit = true;
+                }
+                KeyCode::Char('l') => self.show_logs = !self.show_logs,
+                KeyCode::Char('a') if self.stream.len() > 0 => {
+                    let device = &self.stream[0];
+
+                    let new_mode = if let Some(anc_mode) = &device.anc_mode {
+                        match anc_mode.anc_mode {
+                            AncMode::Off => AncPayload {
+                                anc_mode: AncMode::AmbiantMode,
+                                focus_on_voice: false,
+                                ambiant_level: 17,
+                            },
+                            AncMode::AmbiantMode => AncPayload {
+                                anc_mode: AncMode::On,
+                                focus_on_voice: false,
+                                ambiant_level: 0,
+                            },
+                            AncMode::On => AncPayload {
+                                anc_mode: AncMode::Wind,
+                                focus_on_voice: false,
+                                ambiant_level: 0,
+                            },
+                            AncMode::Wind => AncPayload {
+                                anc_mode: AncMode::Off,
+                                focus_on_voice: false,
+                                ambiant_level: 0,
+                            },
                         }
-                        KeyCode::Char('l') => self.show_logs = !self.show_logs,
-                        KeyCode::Char('a') => {
-                            if self.stream.len() > 0 {
-                                let device = &self.stream[0];
-
-                                let new_mode = if let Some(anc_mode) = &device.anc_mode {
-                                    match anc_mode.anc_mode {
-                                        AncMode::Off => AncPayload {
-                                            anc_mode: AncMode::AmbiantMode,
-                                            focus_on_voice: false,
-                                            ambiant_level: 17,
-                                        },
-                                        AncMode::AmbiantMode => AncPayload {
-                                            anc_mode: AncMode::On,
-                                            focus_on_voice: false,
-                                            ambiant_level: 0,
-                                        },
-                                        AncMode::On => AncPayload {
-                                            anc_mode: AncMode::Wind,
-                                            focus_on_voice: false,
-                                            ambiant_level: 0,
-                                        },
-                                        AncMode::Wind => AncPayload {
-                                            anc_mode: AncMode::Off,
-                                            focus_on_voice: false,
-                                            ambiant_level: 0,
-                                        },
-                                    }
-                                } else {
-                                    AncPayload {
-                                        anc_mode: AncMode::On,
-                                        focus_on_voice: false,
-                                        ambiant_level: 0,
-                                    }
-                                };
-
-                                device
-                                    .device
-                                    .as_ref()
-                                    .send(PacketContent::Command1(
-                                        PayloadCommand1::AmbientSoundControlSet(new_mode),
-                                    ))
-                                    .await?;
-                            }
+                    } else {
+                        AncPayload {
+                            anc_mode: AncMode::On,
+                            focus_on_voice: false,
+                            ambiant_level: 0,
                         }
-                        _ => (),
-                    }
+                    };
+
+                    device
+                        .device
+                        .as_ref()
+                        .send(PacketContent::Command1(
+                            PayloadCommand1::AmbientSoundControlSet(new_mode),
+                        ))
+                        .await?;
                 }
-            }
+                KeyCode::Char('e') if self.stream.len() > 0 => {
+                    let device = &self.stream[0];
+
+                    let preset = match device.equalizer.as_ref().map(|e| e.preset) {
+                        Some(EqPreset::Off) | None => EqPreset::Bright,
+                        Some(EqPreset::Bright) => EqPreset::Excited,
+                        Some(EqPreset::Excited) => EqPreset::Mellow,
+                        Some(EqPreset::Mellow) => EqPreset::Relaxed,
+                        Some(EqPreset::Relaxed) => EqPreset::Vocal,
+                        Some(EqPreset::Vocal) => EqPreset::TrebleBoost,
+                        Some(EqPreset::TrebleBoost) => EqPreset::BassBoost,
+                        Some(EqPreset::BassBoost) => EqPreset::Speech,
+                        Some(EqPreset::Speech) => EqPreset::Custom1,
+                        Some(EqPreset::Custom1) => EqPreset::Custom2,
+                        Some(EqPreset::Manual | EqPreset::Custom2) => EqPreset::Off,
+                    };
+
+                    device
+                        .device
+                        .as_ref()
+                        .send(PacketContent::Command1(PayloadCommand1::EqualizerSet(
+                            EqualizerPayload {
+                                preset,
+                                bands: None,
+                            },
+                        )))
+                        .await?;
+                }
+                KeyCode::Char('p') if self.stream.len() > 0 => {
+                    self.stream[0].device.as_ref().power_off().await?;
+                }
+                KeyCode::Char('d') if self.stream.len() > 0 => {
+                    let device = &self.stream[0];
+
+                    let mode = match device.upsampling {
+                        Some(UpsamplingMode::Off) => UpsamplingMode::On,
+                        Some(UpsamplingMode::On | UpsamplingMode::Auto) | None => {
+                            UpsamplingMode::Off
+                        }
+                    };
+
+                    device
+                        .device
+                        .as_ref()
+                        .send(PacketContent::Command1(
+                            PayloadCommand1::AudioUpsamplingSet(UpsamplingPayload { mode }),
+                        ))
+                        .await?;
+                }
+                _ => (),
+            },
             CrosstermEvent::Resize(_x, _y) => {}
             _ => {}
         }
@@ -160,7 +209,10 @@ impl App {
             {
                 let title_block = Block::default()
                     .borders(Borders::ALL)
-                    .title(device.device.name())
+                    .title(match device.device.model() {
+                        Some(model) => format!("{} ({})", device.device.name(), model),
+                        None => device.device.name().to_owned(),
+                    })
                     .style(Style::default());
 
                 let chunks = Layout::default()
@@ -169,6 +221,8 @@ impl App {
                         Constraint::Length(3),
                         Constraint::Length(3),
                         Constraint::Length(3),
+                        Constraint::Length(3),
+                        Constraint::Length(3),
                     ])
                     .split(title_block.inner(chunks[0]));
                 frame.render_widget(title_block, area);
@@ -184,6 +238,45 @@ impl App {
                 )))
                 .block(block);
                 frame.render_widget(title, chunks[0]);
+
+                let block = Block::new()
+                    .title(vec!["e".red(), Span::raw("qualizer")])
+                    .borders(Borders::ALL)
+                    .style(Style::default());
+
+                let equalizer = Paragraph::new(Text::raw(match &device.equalizer {
+                    Some(EqualizerPayload {
+                        preset,
+                        bands: Some(bands),
+                    }) => format!(
+                        "{:?} (clear bass {}, bands {:?})",
+                        preset, bands.clear_bass, bands.bands
+                    ),
+                    Some(EqualizerPayload {
+                        preset,
+                        bands: None,
+                    }) => format!("{:?}", preset),
+                    None => "None".to_owned(),
+                }))
+                .block(block);
+                frame.render_widget(equalizer, chunks[2]);
+
+                let block = Block::new()
+                    .title(vec!["d".red(), Span::raw("see")])
+                    .borders(Borders::ALL)
+                    .style(Style::default());
+
+                let upsampling =
+                    Paragraph::new(Text::raw(format!("{:?}", device.upsampling))).block(block);
+                frame.render_widget(upsampling, chunks[3]);
+
+                let block = Block::new()
+                    .title("Codec")
+                    .borders(Borders::ALL)
+                    .style(Style::default());
+
+                let codec = Paragraph::new(Text::raw(format!("{:?}", device.codec))).block(block);
+                frame.render_widget(codec, chunks[4]);
                 {
                     let mut constraints = if let Some(d) = &device.battery_device {
                         match d {
@@ -1,22 +1,32 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use bluer::rfcomm::Stream;
 use futures::Future;
-use sony_protocol::v1::{Packet, PacketContent};
+use sony_protocol::{
+    v1::{
+        AudioCodec, AutoPowerOff, AutoPowerOffButtonPayload, AutoPowerOffButtonType,
+        ButtonFunction, JsonPayload, NcAmbButtonMode, NcOptimizerStart, NcOptimizerState,
+        NcOptimizerStatus, Packet, PacketContent, PayloadCommand1, SpeakToChatConfig,
+        TouchSensorPayload, TouchSensorType, VolumePayload,
+    },
+    Timestamp,
+};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     sync::{
-        broadcast::{self, Receiver as BroadcastReceiver, Sender as BroadcastSender},
+        broadcast::{
+            self, error::RecvError, Receiver as BroadcastReceiver, Sender as BroadcastSender,
+        },
         mpsc::{self, Receiver as MpscReceiver, Sender as MspcSender},
         oneshot::{self, Receiver as OneshotReceiver, Sender as OneshotSender},
     },
     time::{self, Instant},
 };
-use tracing::trace;
+use tracing::{info, trace, warn};
 
 pub struct SonyDevice {
-    pub packets_queries: MspcSender<(PacketContent, OneshotSender<()>)>,
+    pub packets_queries: MspcSender<(PacketContent, OneshotSender<anyhow::Result<()>>)>,
     pub packets_receiver: BroadcastReceiver<Packet>,
 }
 
@@ -36,7 +46,7 @@ impl SonyDevice {
     pub fn new(device_stream: Stream) -> (Self, impl Future<Output = anyhow::Result<()>>) {
         let (sender, receiver) = mpsc::channel(1);
 
-        let (broadcast_sender, broadcast_receiver) = broadcast::channel(1);
+        let (broadcast_sender, broadcast_receiver) = broadcast::channel(16);
 
         let thiz = Self {
             packets_queries: sender,
@@ -47,71 +57,387 @@ impl SonyDevice {
         (thiz, run)
     }
 
-    pub async fn send(&self, content: PacketContent) -> anyhow::Result<OneshotReceiver<()>> {
+    pub async fn send(
+        &self,
+        content: PacketContent,
+    ) -> anyhow::Result<OneshotReceiver<anyhow::Result<()>>> {
         let (sender, receiver) = oneshot::channel();
         self.packets_queries.send((content, sender)).await?;
         Ok(receiver)
     }
 
+    /// Set the volume of the headset itself, up to [`VolumePayload::MAX`], independently
+    /// from the volume of the audio source.
+    pub async fn set_volume(
+        &self,
+        level: u8,
+    ) -> anyhow::Result<OneshotReceiver<anyhow::Result<()>>> {
+        anyhow::ensure!(
+            level <= VolumePayload::MAX,
+            "volume {} above {}",
+            level,
+            VolumePayload::MAX
+        );
+
+        self.send(PacketContent::Command1(PayloadCommand1::VolumeSet(
+            VolumePayload { level },
+        )))
+        .await
+    }
+
+    /// Turn the headset off, the device loop then ends without error when it disconnects.
+    pub async fn power_off(&self) -> anyhow::Result<OneshotReceiver<anyhow::Result<()>>> {
+        self.send(PacketContent::Command1(PayloadCommand1::PowerOff))
+            .await
+    }
+
+    /// Read the codec negotiated with the audio source.
+    pub async fn get_audio_codec(&mut self) -> anyhow::Result<AudioCodec> {
+        self.request(
+            PacketContent::Command1(PayloadCommand1::AudioCodecRequest),
+            |content| match content {
+                PacketContent::Command1(PayloadCommand1::AudioCodecReply(codec)) => Some(codec),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Request the JSON payload of `json_type`, which newer firmwares use for their
+    /// structured settings.
+    pub async fn get_json(&mut self, json_type: u8) -> anyhow::Result<JsonPayload> {
+        self.request(
+            PacketContent::Command1(PayloadCommand1::JsonGet(json_type)),
+            |content| match content {
+                PacketContent::Command1(PayloadCommand1::JsonRet(payload))
+                    if payload.json_type == json_type =>
+                {
+                    Some(payload)
+                }
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Choose when the headset turns itself off.
+    pub async fn set_auto_power_off(
+        &self,
+        auto_power_off: AutoPowerOff,
+    ) -> anyhow::Result<OneshotReceiver<anyhow::Result<()>>> {
+        self.send(PacketContent::Command1(
+            PayloadCommand1::AutomaticPowerOffButtonModeSet(
+                AutoPowerOffButtonPayload::AutoPowerOff(auto_power_off),
+            ),
+        ))
+        .await
+    }
+
+    /// Enable or disable speak-to-chat.
+    pub async fn set_speak_to_chat(
+        &self,
+        enabled: bool,
+    ) -> anyhow::Result<OneshotReceiver<anyhow::Result<()>>> {
+        self.send(PacketContent::Command1(
+            PayloadCommand1::AutomaticPowerOffButtonModeSet(
+                AutoPowerOffButtonPayload::SpeakToChat { enabled },
+            ),
+        ))
+        .await
+    }
+
+    /// Read the speak-to-chat sensitivity, voice focus and timeout.
+    pub async fn get_speak_to_chat_config(&mut self) -> anyhow::Result<SpeakToChatConfig> {
+        self.request(
+            PacketContent::Command1(PayloadCommand1::SpeakToChatConfigGet),
+            |content| match content {
+                PacketContent::Command1(PayloadCommand1::SpeakToChatConfigRet(config)) => {
+                    Some(config)
+                }
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Change the speak-to-chat sensitivity, voice focus and timeout.
+    pub async fn set_speak_to_chat_config(
+        &self,
+        config: SpeakToChatConfig,
+    ) -> anyhow::Result<OneshotReceiver<anyhow::Result<()>>> {
+        self.send(PacketContent::Command1(
+            PayloadCommand1::SpeakToChatConfigSet(config),
+        ))
+        .await
+    }
+
+    /// Read whether the touch panel is enabled.
+    pub async fn get_touch_sensor(
+        &mut self,
+        sensor_type: TouchSensorType,
+    ) -> anyhow::Result<TouchSensorPayload> {
+        self.request(
+            PacketContent::Command1(PayloadCommand1::TouchSensorGet(sensor_type)),
+            |content| match content {
+                PacketContent::Command1(PayloadCommand1::TouchSensorRet(payload))
+                    if payload.sensor_type() == sensor_type =>
+                {
+                    Some(payload)
+                }
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Enable or disable the touch panel.
+    pub async fn set_touch_sensor(
+        &self,
+        payload: TouchSensorPayload,
+    ) -> anyhow::Result<OneshotReceiver<anyhow::Result<()>>> {
+        self.send(PacketContent::Command1(PayloadCommand1::TouchSensorSet(
+            payload,
+        )))
+        .await
+    }
+
+    /// Read the functions assigned to the left and right buttons or touch panels.
+    pub async fn get_button_modes(&mut self) -> anyhow::Result<(ButtonFunction, ButtonFunction)> {
+        self.request(
+            PacketContent::Command1(PayloadCommand1::AutomaticPowerOffButtonModeGet(
+                AutoPowerOffButtonType::ButtonModes,
+            )),
+            |content| match content {
+                PacketContent::Command1(PayloadCommand1::AutomaticPowerOffButtonModeRet(
+                    AutoPowerOffButtonPayload::ButtonModes { left, right },
+                )) => Some((left, right)),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Assign functions to the left and right buttons or touch panels.
+    pub async fn set_button_modes(
+        &self,
+        left: ButtonFunction,
+        right: ButtonFunction,
+    ) -> anyhow::Result<OneshotReceiver<anyhow::Result<()>>> {
+        self.send(PacketContent::Command1(
+            PayloadCommand1::AutomaticPowerOffButtonModeSet(
+                AutoPowerOffButtonPayload::ButtonModes { left, right },
+            ),
+        ))
+        .await
+    }
+
+    /// Read the ambient sound modes the NC/AMB button cycles through.
+    pub async fn get_nc_amb_button(&mut self) -> anyhow::Result<NcAmbButtonMode> {
+        self.request(
+            PacketContent::Command1(PayloadCommand1::AutomaticPowerOffButtonModeGet(
+                AutoPowerOffButtonType::NcAmbButton,
+            )),
+            |content| match content {
+                PacketContent::Command1(PayloadCommand1::AutomaticPowerOffButtonModeRet(
+                    AutoPowerOffButtonPayload::NcAmbButton(mode),
+                )) => Some(mode),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Choose the ambient sound modes the NC/AMB button cycles through.
+    pub async fn set_nc_amb_button(
+        &self,
+        mode: NcAmbButtonMode,
+    ) -> anyhow::Result<OneshotReceiver<anyhow::Result<()>>> {
+        self.send(PacketContent::Command1(
+            PayloadCommand1::AutomaticPowerOffButtonModeSet(
+                AutoPowerOffButtonPayload::NcAmbButton(mode),
+            ),
+        ))
+        .await
+    }
+
+    /// Run the noise cancelling optimizer, resolving with its result once it finished.
+    pub async fn run_nc_optimizer(&mut self) -> anyhow::Result<NcOptimizerState> {
+        // Fail at once if the start command is not acknowledged.
+        self.send(PacketContent::Command1(
+            PayloadCommand1::NoiseCancellingOptimizerStart(NcOptimizerStart { start: true }),
+        ))
+        .await?
+        .await??;
+
+        let mut started = false;
+        let finished = self.wait_for(|content| match content {
+            PacketContent::Command1(PayloadCommand1::NoiseCancellingOptimizerStatus(status)) => {
+                match status {
+                    NcOptimizerStatus::Finished => Some(Ok(())),
+                    NcOptimizerStatus::NotRunning if started => {
+                        Some(Err(anyhow!("optimization stopped before finishing")))
+                    }
+                    NcOptimizerStatus::NotRunning => None,
+                    _ => {
+                        started = true;
+                        None
+                    }
+                }
+            }
+            _ => None,
+        });
+        time::timeout(Duration::from_secs(120), finished)
+            .await
+            .context("optimization timed out")???;
+
+        self.request(
+            PacketContent::Command1(PayloadCommand1::NoiseCancellingOptimizerStateGet),
+            |content| match content {
+                PacketContent::Command1(
+                    PayloadCommand1::NoiseCancellingOptimizerStateRet(state)
+                    | PayloadCommand1::NoiseCancellingOptimizerStateNotify(state),
+                ) => Some(state),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Send `content` and wait for the reply, which `f` extracts from the received packets.
+    /// Fails without waiting for the reply if `content` is not acknowledged.
+    pub async fn request<T>(
+        &mut self,
+        content: PacketContent,
+        f: impl FnMut(PacketContent) -> Option<T>,
+    ) -> anyhow::Result<T> {
+        self.send(content).await?.await??;
+
+        time::timeout(Duration::from_secs(2), self.wait_for(f))
+            .await
+            .context("no reply received")?
+    }
+
+    /// Wait for the first received packet for which `f` returns a value.
+    pub async fn wait_for<T>(
+        &mut self,
+        mut f: impl FnMut(PacketContent) -> Option<T>,
+    ) -> anyhow::Result<T> {
+        loop {
+            match self.packets_receiver.recv().await {
+                Ok(packet) => {
+                    if let Some(value) = f(packet.content) {
+                        return Ok(value);
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => warn!("missed {} received packets", skipped),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
     pub async fn run(
         mut device_stream: Stream,
-        mut next_packets: MpscReceiver<(PacketContent, OneshotSender<()>)>,
+        mut next_packets: MpscReceiver<(PacketContent, OneshotSender<anyhow::Result<()>>)>,
         sender: BroadcastSender<Packet>,
     ) -> anyhow::Result<()> {
         let mut device_session = sony_protocol::Device::default();
         let mut receive_buffer = [0u8; 1024];
 
+        // Origin of the timestamps given to the protocol device.
+        let start = Instant::now();
+
         let next_poll = time::sleep(Duration::from_secs(0));
         tokio::pin!(next_poll);
 
-        let mut next_packet = None;
+        let mut pending_packets = HashMap::new();
+
+        // The headset drops the connection once it has received the power off command,
+        // possibly before its ACK reaches us.
+        let mut power_off = None;
+        let mut power_off_sent = false;
 
         loop {
             let read = tokio::select! {
-                res = device_stream.read(&mut receive_buffer) => {
-                    let num_read = res.context("receive failed")?;
-                    Some(num_read)
-                }
-                next = next_packets.recv(), if next_packet.is_none() => {
+                res = device_stream.read(&mut receive_buffer) => match res {
+                    Ok(0) if power_off_sent => {
+                        info!("device powered off");
+                        return Ok(());
+                    }
+                    Ok(0) => return Err(anyhow!("device disconnected")),
+                    Ok(num_read) => Some(num_read),
+                    Err(e) if power_off_sent => {
+                        info!("device powered off ({})", e);
+                        return Ok(());
+                    }
+                    Err(e) => return Err(e).context("receive failed"),
+                },
+                next = next_packets.recv(), if !device_session.is_queue_full() => {
                     if let Some((p, c)) = next {
-                        device_session.send_packet(p)?;
-                        next_packet = Some(c);
+                        let is_power_off =
+                            matches!(p, PacketContent::Command1(PayloadCommand1::PowerOff));
+                        let id = device_session.send_packet(p)?;
+                        if is_power_off {
+                            power_off = Some(id);
+                        }
+                        pending_packets.insert(id, c);
                     }
                     None
                 },
                 _ = &mut next_poll => None,
             };
 
-            if let Some(num_read) = read {
-                device_session.received_packet(&receive_buffer[..num_read])?;
-            }
+            let mut received = &receive_buffer[..read.unwrap_or(0)];
 
             let wait = loop {
-                let state = device_session.poll()?;
-                trace!("run_loop: state = {:?}", state);
+                if !received.is_empty() {
+                    let consumed = device_session.received_packet(received)?;
+                    received = &received[consumed..];
+                }
 
-                match state {
-                    sony_protocol::State::WaitingPacket(next) => {
-                        break next;
-                    }
-                    sony_protocol::State::ReceivedPacket(p) => match p.content {
-                        PacketContent::Ack => {
-                            if let Some(c) = next_packet.take() {
-                                _ = c.send(());
-                            }
+                let wait = loop {
+                    let now = Timestamp::from_millis(start.elapsed().as_millis() as u64);
+                    let state = device_session.poll(now)?;
+                    trace!("run_loop: state = {:?}", state);
+
+                    match state {
+                        sony_protocol::State::WaitingPacket(next) => {
+                            break next;
                         }
-                        _ => {
+                        sony_protocol::State::ReceivedPacket(p) => {
                             tracing::trace!("run_loop: sending to broadcast packet={:?}", p);
                             sender.send(p)?;
                         }
-                    },
-                    sony_protocol::State::SendPacket(p) => {
-                        device_stream.write(p).await?;
-                    }
+                        sony_protocol::State::SendPacket(p, id) => {
+                            match device_stream.write_all(p).await {
+                                Ok(()) => power_off_sent |= id.is_some() && id == power_off,
+                                Err(e) if power_off_sent => {
+                                    info!("device powered off ({})", e);
+                                    return Ok(());
+                                }
+                                Err(e) => return Err(e.into()),
+                            }
+                        }
+                        sony_protocol::State::PacketAcked(id) => {
+                            if let Some(c) = pending_packets.remove(&id) {
+                                _ = c.send(Ok(()));
+                            }
+                        }
+                        sony_protocol::State::PacketFailed(id) => {
+                            if let Some(c) = pending_packets.remove(&id) {
+                                _ = c.send(Err(anyhow!("failed to send packet {:?}", id)));
+                            }
+                        }
+                    };
                 };
+
+                if received.is_empty() {
+                    break wait;
+                }
             };
             if let Some(wait) = wait {
-                next_poll.as_mut().reset(wait.into());
+                next_poll
+                    .as_mut()
+                    .reset(start + Duration::from_millis(wait.as_millis()));
             } else {
                 next_poll
                     .as_mut()